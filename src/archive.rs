@@ -0,0 +1,117 @@
+// The archives whose entries are converted: zips and tars behind one interface.
+
+use crate::{ArchiveEntries, CentralRecord, EntryHandle, EntryMetadata};
+use std::io;
+
+/// One entry of an [`ArchiveReader`]; its data is read only when asked for.
+pub trait ArchiveEntry {
+    fn name(&self) -> &str;
+
+    fn metadata(&self) -> EntryMetadata;
+
+    /// Whether `EntryMetadata::crc32` holds the CRC-32 of the data.
+    fn has_crc32(&self) -> bool;
+
+    /// The fields of the entry beyond its metadata, e.g. its comment.
+    fn central_record(&self) -> Result<CentralRecord, io::Error>;
+
+    /// Offset of the data of the entry as stored in the archive, e.g. [`EntryHandle::data_offset`].
+    fn data_offset(&self) -> Result<u64, io::Error>;
+
+    /// See [`EntryHandle::read_body_with_password`].
+    fn read_body_with_password(
+        &self,
+        buf: &mut Vec<u8>,
+        password: Option<&[u8]>,
+    ) -> Result<(), io::Error>;
+
+    /// See [`EntryHandle::read_body_prefix`].
+    fn read_body_prefix(
+        &self,
+        buf: &mut Vec<u8>,
+        len: u64,
+        password: Option<&[u8]>,
+    ) -> Result<(), io::Error>;
+
+    /// See [`EntryHandle::read_body_chunks`].
+    fn read_body_chunks(
+        &self,
+        chunk_len: usize,
+        password: Option<&[u8]>,
+        on_chunk: &mut dyn FnMut(&[u8]) -> Result<(), io::Error>,
+    ) -> Result<(), io::Error>;
+}
+
+/// An archive converted entry by entry, e.g. an [`ArchiveEntries`] or a [`TarEntries`](crate::TarEntries).
+pub trait ArchiveReader {
+    /// The entries in archive order.
+    fn entries(
+        &self,
+    ) -> Box<dyn Iterator<Item = Result<Box<dyn ArchiveEntry + '_>, io::Error>> + '_>;
+
+    /// The comment of the archive itself; empty for formats without one.
+    fn comment(&self) -> Result<String, io::Error>;
+}
+
+impl ArchiveEntry for EntryHandle<'_> {
+    fn name(&self) -> &str {
+        EntryHandle::name(self)
+    }
+
+    fn metadata(&self) -> EntryMetadata {
+        EntryHandle::metadata(self)
+    }
+
+    fn has_crc32(&self) -> bool {
+        EntryHandle::has_crc32(self)
+    }
+
+    fn central_record(&self) -> Result<CentralRecord, io::Error> {
+        EntryHandle::central_record(self)
+    }
+
+    fn data_offset(&self) -> Result<u64, io::Error> {
+        EntryHandle::data_offset(self)
+    }
+
+    fn read_body_with_password(
+        &self,
+        buf: &mut Vec<u8>,
+        password: Option<&[u8]>,
+    ) -> Result<(), io::Error> {
+        EntryHandle::read_body_with_password(self, buf, password)
+    }
+
+    fn read_body_prefix(
+        &self,
+        buf: &mut Vec<u8>,
+        len: u64,
+        password: Option<&[u8]>,
+    ) -> Result<(), io::Error> {
+        EntryHandle::read_body_prefix(self, buf, len, password)
+    }
+
+    fn read_body_chunks(
+        &self,
+        chunk_len: usize,
+        password: Option<&[u8]>,
+        on_chunk: &mut dyn FnMut(&[u8]) -> Result<(), io::Error>,
+    ) -> Result<(), io::Error> {
+        EntryHandle::read_body_chunks(self, chunk_len, password, on_chunk)
+    }
+}
+
+impl ArchiveReader for ArchiveEntries<'_> {
+    fn entries(
+        &self,
+    ) -> Box<dyn Iterator<Item = Result<Box<dyn ArchiveEntry + '_>, io::Error>> + '_> {
+        Box::new(
+            self.handles()
+                .map(|handle| handle.map(|handle| Box::new(handle) as Box<dyn ArchiveEntry>)),
+        )
+    }
+
+    fn comment(&self) -> Result<String, io::Error> {
+        ArchiveEntries::comment(self)
+    }
+}
@@ -0,0 +1,287 @@
+// Async counterparts of the conversion functions, for embedding in tokio services.
+//
+// The inputs are read and the records written asynchronously; the conversion of an
+// archive itself is blocking (decompression, external and partition files, sinks), its
+// records staged in memory. It borrows the `Options`, so it cannot be moved to
+// `spawn_blocking`; it runs in `block_in_place` instead, which needs a multi-thread runtime.
+
+use crate::{
+    Error, InputFormat, JsonArrayWriter, Options, OutputFormat, ReadError, RunStats,
+    STDIN_ZIP_NAME, StatsRecorder, ZipInput, input,
+};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::task;
+
+/// Same as [`rdr2buf`](crate::rdr2buf) for an [`AsyncRead`].
+pub async fn rdr2buf_async<R>(rdr: R, buf: &mut Vec<u8>, limit: u64) -> Result<(), ReadError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut taken = rdr.take(limit.saturating_add(1));
+    buf.clear();
+    taken.read_to_end(buf).await?;
+    if buf.len() as u64 > limit {
+        return Err(ReadError::SizeLimitExceeded);
+    }
+    Ok(())
+}
+
+/// Same as [`filename2buf`](crate::filename2buf), opening and reading the file with tokio.
+pub async fn file2buf_async<P>(filename: P, buf: &mut Vec<u8>, limit: u64) -> Result<(), ReadError>
+where
+    P: AsRef<Path>,
+{
+    let f = tokio::fs::File::open(filename).await?;
+    rdr2buf_async(f, buf, limit).await
+}
+
+// The records of the inputs converted so far, not yet written to the async writer.
+enum Staged {
+    Records(Vec<u8>),
+    Array(JsonArrayWriter<Vec<u8>>),
+}
+
+impl Staged {
+    fn new(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::JsonArray => Staged::Array(JsonArrayWriter::new(Vec::new())),
+            _ => Staged::Records(Vec::new()),
+        }
+    }
+
+    fn bytes(&mut self) -> &mut Vec<u8> {
+        match self {
+            Staged::Records(bytes) => bytes,
+            Staged::Array(array) => array.get_mut(),
+        }
+    }
+
+    // Writes out the staged bytes, keeping the buffer for the next input.
+    async fn drain<W>(&mut self, wtr: &mut W) -> Result<(), io::Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let bytes = self.bytes();
+        wtr.write_all(bytes).await?;
+        bytes.clear();
+        Ok(())
+    }
+
+    // Closes the array, if any, and writes out the rest.
+    async fn finish<W>(self, wtr: &mut W) -> Result<(), io::Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let rest = match self {
+            Staged::Records(bytes) => bytes,
+            Staged::Array(array) => array.finish()?,
+        };
+        wtr.write_all(&rest).await?;
+        wtr.flush().await
+    }
+}
+
+impl Write for Staged {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Staged::Records(bytes) => Write::write(bytes, buf),
+            Staged::Array(array) => array.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Runs the blocking `convert` with the records staged in `staged`, the other tasks of the
+// worker moved to other workers meanwhile; on a current-thread runtime they wait for it.
+fn stage<F>(staged: &mut Staged, convert: F) -> Result<(), io::Error>
+where
+    F: FnOnce(&mut BufWriter<&mut Staged>) -> Result<(), io::Error>,
+{
+    let run = || {
+        let mut wtr = BufWriter::new(staged);
+        convert(&mut wtr)?;
+        wtr.flush()
+    };
+    match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+        Ok(RuntimeFlavor::MultiThread) => task::block_in_place(run),
+        _ => run(),
+    }
+}
+
+/// Same as [`buf2zip2blobs2jsons2writer`](crate::buf2zip2blobs2jsons2writer), writing to an [`AsyncWrite`].
+///
+/// The conversion blocks the worker thread, in `block_in_place` on a multi-thread runtime;
+/// on a current-thread runtime it blocks the runtime until it is done.
+pub async fn buf2zip2blobs2jsons2writer_async<W>(
+    zip_name: &str,
+    zipdata: &[u8],
+    options: &Options<'_>,
+    wtr: &mut W,
+) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut staged = Staged::new(OutputFormat::Ndjson);
+    stage(&mut staged, |out| {
+        crate::buf2zip2blobs2jsons2writer(zip_name, zipdata, options, out).map_err(io::Error::from)
+    })?;
+    staged.finish(wtr).await?;
+    Ok(())
+}
+
+/// Same as [`rdr2zip2blobs2jsons2writer`](crate::rdr2zip2blobs2jsons2writer) for an [`AsyncRead`]
+/// (e.g. an uploaded body) and an [`AsyncWrite`].
+///
+/// `output_compression` is not applied; wrap `wtr` in an async encoder instead.
+///
+/// The conversion blocks as in [`buf2zip2blobs2jsons2writer_async`].
+pub async fn rdr2zip2blobs2jsons2writer_async<R, W>(
+    zip_name: &str,
+    rdr: R,
+    buf: &mut Vec<u8>,
+    options: &Options<'_>,
+    wtr: &mut W,
+) -> Result<RunStats, Error>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let loaded = rdr2buf_async(rdr, buf, options.max_zip_size).await;
+    let mut staged = Staged::new(options.output_format);
+    let stats = crate::with_stats(options, |options| {
+        let input = ZipInput::from(zip_name.to_string());
+        stage(&mut staged, |out| {
+            crate::loaded2zip2blobs2jsons2writer(&input, &loaded, buf, options, out)
+        })
+    })?;
+    staged.finish(wtr).await?;
+    Ok(stats)
+}
+
+/// Async counterpart of [`stdin2zfilenames2zip2blobs2jsons2writer`](crate::stdin2zfilenames2zip2blobs2jsons2writer)
+/// reading the inputs (in `options.input_format`) from `rdr`.
+///
+/// The archives are read with tokio and converted one at a time in the order given:
+/// `jobs`, `prefetch`, `order` and `streaming` do not apply, nor does `output_compression`.
+/// Each conversion blocks as in [`buf2zip2blobs2jsons2writer_async`].
+pub async fn rdr2zfilenames2zip2blobs2jsons2writer_async<R, W>(
+    rdr: R,
+    options: &Options<'_>,
+    wtr: &mut W,
+) -> Result<RunStats, Error>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf: Vec<u8> = Vec::with_capacity((1 << 20) * 2);
+    let stats = match options.input_format {
+        InputFormat::Bytes => {
+            rdr2zip2blobs2jsons2writer_async(STDIN_ZIP_NAME, rdr, &mut buf, options, wtr).await?
+        }
+        InputFormat::Lines | InputFormat::Jsonl | InputFormat::Entries => {
+            let start = Instant::now();
+            let recorder = StatsRecorder::default();
+            let counted = Options {
+                stats: Some(&recorder),
+                ..*options
+            };
+            zinputs2writer_async(rdr, &mut buf, &counted, wtr).await?;
+            let stats = recorder.finish(start.elapsed());
+            if let Some(outer) = options.stats {
+                outer.add(&stats);
+            }
+            stats
+        }
+    };
+
+    if let Some(partitions) = options.partitions {
+        partitions.flush()?;
+    }
+    Ok(stats)
+}
+
+// Converts the inputs named by the lines of `rdr`, one at a time.
+async fn zinputs2writer_async<R, W>(
+    rdr: R,
+    buf: &mut Vec<u8>,
+    options: &Options<'_>,
+    wtr: &mut W,
+) -> Result<(), io::Error>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut staged = Staged::new(options.output_format);
+    let mut lines = rdr.lines();
+    // The input of the `InputFormat::Entries` lines read so far.
+    let mut pending: Option<ZipInput> = None;
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                options.report_unrecoverable(e, None)?;
+                options.zip_done();
+                continue;
+            }
+        };
+        let input = match options.input_format {
+            InputFormat::Jsonl | InputFormat::Entries if line.trim().is_empty() => continue,
+            InputFormat::Jsonl => match serde_json::from_str::<ZipInput>(&line) {
+                Ok(input) => input,
+                Err(e) => {
+                    let e = io::Error::new(io::ErrorKind::InvalidData, e);
+                    options.report_unrecoverable(e, None)?;
+                    options.zip_done();
+                    continue;
+                }
+            },
+            InputFormat::Entries => match input::push_entry_line(&mut pending, &line) {
+                Some(input) => input,
+                None => continue,
+            },
+            _ => ZipInput::from(line),
+        };
+        zinput2staged_async(&input, buf, options, &mut staged, wtr).await?;
+    }
+    if let Some(input) = pending {
+        zinput2staged_async(&input, buf, options, &mut staged, wtr).await?;
+    }
+    staged.finish(wtr).await
+}
+
+// Converts one input into `staged`, then writes it out.
+async fn zinput2staged_async<W>(
+    input: &ZipInput,
+    buf: &mut Vec<u8>,
+    options: &Options<'_>,
+    staged: &mut Staged,
+    wtr: &mut W,
+) -> Result<(), io::Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    if options.unchanged(input) {
+        return Ok(());
+    }
+
+    let loaded = file2buf_async(&input.path, buf, options.max_zip_size).await;
+    stage(staged, |out| {
+        match crate::loaded2zip2blobs2jsons2writer(input, &loaded, buf, options, out) {
+            Err(e) => options.report_unrecoverable(e, Some(input.path.as_str())),
+            Ok(()) => Ok(()),
+        }
+    })?;
+    staged.drain(wtr).await?;
+    options.zip_done();
+    Ok(())
+}
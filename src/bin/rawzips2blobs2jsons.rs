@@ -1,18 +1,34 @@
-use clap::Parser;
-use rs_rawzips2blobs2jsons::stdin2zfilenames2zip2blobs2jsons2stdout;
+use chrono::FixedOffset;
+use clap::{Parser, Subcommand, ValueEnum};
+use rs_rawzips2blobs2jsons::{
+    ArchiveRecordMode, BodyEncoding, BodyMode, Checkpoint, Checksum, ContentTypeDetection,
+    DEFAULT_MAX_OPEN_PARTITIONS, EncodingRule, EntryOrder, ErrorPolicy, FieldMap, FieldRename,
+    FilenameEncoding, Glob, InputFormat, InputOrder, Layout, LogFormat, LogSink,
+    MAX_ITEM_BYTES_DEFAULT, MAX_ZIP_BYTES_DEFAULT, Options, OutputCompression, OutputFormat,
+    OutputLimit, OutputMode, OversizePolicy, PartitionedOutput, PathPattern, Progress, ProgressLog,
+    RotatingWriter, RunStats, dir2checks2writer, dir2zip2blobs2jsons2writer,
+    manifest2zip2blobs2jsons2writer, stdin2jsons2violations2stdout, stdin2zfilenames2checks2writer,
+    stdin2zfilenames2zip2blobs2jsons2writer,
+};
+#[cfg(feature = "http-sink")]
+use rs_rawzips2blobs2jsons::{BlobSink, HttpSink};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
 use std::process;
-
-const MAX_ZIP_BYTES_DEFAULT: u64 = 1 << 20; // 1MiB
-const MAX_ITEM_BYTES_DEFAULT: u64 = 1 << 17; // 128KiB
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(
     author,
     version,
     about = "Converts zip archives into a stream of JSON blobs.",
-    long_about = "Reads zip filenames from stdin (one per line), and for each file inside the zips, outputs a JSON blob. The blob contains metadata and base64-encoded content."
+    long_about = "Reads zip filenames from stdin (one per line), and for each file inside the zips, outputs a JSON blob. Tar archives, and gzip or (with the zstd feature) zstd-compressed zips and tars, are converted the same way. The blob contains metadata and base64-encoded content."
 )]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(
         long,
         default_value_t = MAX_ZIP_BYTES_DEFAULT,
@@ -23,10 +39,32 @@ struct Cli {
     #[arg(
         long,
         default_value_t = MAX_ITEM_BYTES_DEFAULT,
-        help = "Max size in bytes for a file within a zip (skipped if exceeded)."
+        help = "Max uncompressed size in bytes for a file within a zip (see --oversize-policy)."
     )]
     item_size_max: u64,
 
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Emit at most N records per zip, nested entries and parts included; the rest of the zip is skipped with a warning (an error with --strict)."
+    )]
+    max_entries_per_zip: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Stop converting entries once the records written reach BYTES; the rest of each zip is skipped with a warning (an error with --strict)."
+    )]
+    max_total_output_bytes: Option<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OversizePolicyArg::Skip,
+        help = "What to do with a file larger than --item-size-max: skip it, emit its first bytes, or split it into parts."
+    )]
+    oversize_policy: OversizePolicyArg,
+
     #[arg(
         long,
         default_value = "application/octet-stream",
@@ -41,6 +79,19 @@ struct Cli {
     )]
     item_content_encoding: String,
 
+    #[arg(
+        long,
+        value_name = "GLOB=ENCODING",
+        help = "Content-Encoding of the entries matching the glob, e.g. '*.gz=gzip' (repeatable; the first match wins over --item-content-encoding)."
+    )]
+    encoding_rule: Vec<EncodingRule>,
+
+    #[arg(
+        long,
+        help = "Remove the extension an --encoding-rule matched from the blob names (a.json.gz becomes a.json)."
+    )]
+    strip_encoding_suffix: bool,
+
     #[arg(
         short,
         long,
@@ -48,18 +99,997 @@ struct Cli {
         help = "Enable verbose output (warnings for skipped files)."
     )]
     verbose: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the warnings to this file instead of stderr (implies --verbose)."
+    )]
+    log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Skip the zips this file lists with the same size and mtime, then add the zips converted to it; created if missing."
+    )]
+    state_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = LogFormatArg::Text,
+        help = "text: tab-separated key:value lines; json: one JSON object per line."
+    )]
+    log_format: LogFormatArg,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Add OOXML (docx/xlsx/pptx) core properties to the metadata."
+    )]
+    ooxml: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "ooxml",
+        help = "Add the plain text of OOXML main document parts (requires --ooxml)."
+    )]
+    ooxml_text: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Add the META-INF/MANIFEST.MF main attributes of jar/war/ear inputs (and nested entries) to the metadata."
+    )]
+    jar_manifest: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Add line_count, byte_count and max_line_length fields to text entries."
+    )]
+    text_stats: bool,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Number of zip files to read ahead while converting the current one (0 disables)."
+    )]
+    prefetch: usize,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Escape all non-ASCII characters in output strings as \\uXXXX."
+    )]
+    ascii_only: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Write bodies larger than --external-threshold to this content-addressed directory."
+    )]
+    external_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        requires = "external_dir",
+        help = "Max size in bytes of a body kept inline when --external-dir is set."
+    )]
+    external_threshold: u64,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputModeArg::Flat,
+        help = "flat: one JSON blob per entry; tree: one nested JSON document per zip."
+    )]
+    output_mode: OutputModeArg,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Include the base64 bodies of the files in tree documents."
+    )]
+    tree_bodies: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = InputFormatArg::Lines,
+        help = "lines: one zip filename per line; jsonl: one {\"path\", \"sha256\", \"entries\"} object per line; bytes: stdin is a zip (or tar) itself (ZipName \"-\"); entries: one zip<TAB>entry name per line, converting only the entries listed."
+    )]
+    input_format: InputFormatArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = InputOrderArg::AsGiven,
+        help = "Order in which the zips are processed (anything but as-given reads all of stdin first)."
+    )]
+    order: InputOrderArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = EntryOrderArg::None,
+        help = "Order of the blobs within each zip (anything but none reads all the entry headers of a zip first, up to --zip-size-max bytes)."
+    )]
+    sort_entries: EntryOrderArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = FilenameEncodingArg::Auto,
+        help = "Codepage of the entry names not flagged as UTF-8 (auto: UTF-8 if valid, else cp437); undecodable names also get their bytes in name_raw (base64)."
+    )]
+    filename_encoding: FilenameEncodingArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ArchiveRecordArg::None,
+        help = "Also (or only) emit a blob for each zip itself, with its sha256."
+    )]
+    archive_record: ArchiveRecordArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = LayoutArg::Nested,
+        help = "nested: metadata under \"metadata\"; flat: metadata as top-level metadata_* fields."
+    )]
+    layout: LayoutArg,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Add the {name} captures of this pattern on each zip path (e.g. /data/{tenant}/{*}.zip) to the metadata."
+    )]
+    path_pattern: Option<PathPattern>,
+
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        requires = "output_dir",
+        help = "Write the blobs into <output-dir>/field=value/.../part-NNN.jsonl files (fields: last_modified_date, content_type, ZipName or a metadata key)."
+    )]
+    partition_by: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_MAX_OPEN_PARTITIONS,
+        requires = "partition_by",
+        help = "Keep at most N partition files open, closing the least recently written (reopened to append)."
+    )]
+    partition_max_open: usize,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Write the blobs under this directory: partitioned with --partition-by, else into blobs-NNNNN.jsonl files split by --split-records/--split-bytes."
+    )]
+    output_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "output_dir",
+        conflicts_with = "partition_by",
+        help = "Start the next blobs-NNNNN.jsonl file after N records."
+    )]
+    split_records: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "output_dir",
+        conflicts_with = "partition_by",
+        help = "Start the next blobs-NNNNN.jsonl file once one holds N bytes (checked after each record)."
+    )]
+    split_bytes: Option<u64>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "prefetch",
+        help = "Read the entries from each zip file one at a time instead of loading the whole zip (--zip-size-max then only limits --archive-record)."
+    )]
+    streaming: bool,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Convert only the entries matching this glob (repeatable; a glob without '/' matches the file name)."
+    )]
+    include: Vec<Glob>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Do not convert the entries matching this glob (repeatable)."
+    )]
+    exclude: Vec<Glob>,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        conflicts_with = "prefetch",
+        help = "Number of zip files converted in parallel (output stays in input order)."
+    )]
+    jobs: usize,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DetectContentTypeArg::None,
+        num_args = 0..=1,
+        default_missing_value = "extension",
+        help = "Infer each entry's Content-Type from its extension (sniff: also from magic bytes), falling back to --item-content-type."
+    )]
+    detect_content_type: DetectContentTypeArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormatArg::Ndjson,
+        help = "ndjson: one record per line; json-array: a single JSON array; pretty: indented records."
+    )]
+    output_format: OutputFormatArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = BodyEncodingArg::Base64,
+        help = "Transfer encoding of the bodies (utf8: text entries as is, others in base64)."
+    )]
+    body_encoding: BodyEncodingArg,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ChecksumArg::Crc32,
+        help = "crc32: the CRC-32 of the zip headers only; sha256: also the SHA-256 of each body."
+    )]
+    checksum: ChecksumArg,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with = "input_format",
+        help = "Convert the zips found under this directory (recursively) instead of reading stdin."
+    )]
+    root_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["input_format", "root_dir", "check"],
+        help = "Convert the entries listed in this file, one zip<TAB>entry name per line (as --input-format entries), instead of reading stdin."
+    )]
+    manifest: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "EXT",
+        value_delimiter = ',',
+        default_value = "zip",
+        requires = "root_dir",
+        help = "File extensions of the archives looked for under --root-dir (comma-separated, e.g. zip,tar,tar.gz,tgz)."
+    )]
+    extension: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print a JSON summary of the run (zips and entries converted or skipped, bytes, time) to stderr."
+    )]
+    summary: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Convert the entries of .zip (and .jar/.war/.ear) entries in place of them, named like inner.zip!a.txt."
+    )]
+    recurse_zips: bool,
+
+    #[arg(
+        long,
+        default_value_t = 4,
+        requires = "recurse_zips",
+        help = "Levels of nested zips opened by --recurse-zips."
+    )]
+    max_depth: usize,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Add the compression method, compressed size, Unix mode, external attributes, directory flag and comments of each entry."
+    )]
+    extended_metadata: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Add the offset, compressed size and compression method of the stored data of each entry (with --no-body the data is not read)."
+    )]
+    with_offsets: bool,
+
+    #[arg(
+        long,
+        value_name = "OFFSET",
+        default_value = "UTC",
+        value_parser = parse_offset,
+        allow_hyphen_values = true,
+        help = "Time zone (UTC or e.g. +09:00) of entry times stored only as DOS times."
+    )]
+    dos_timezone: FixedOffset,
+
+    #[arg(
+        long,
+        conflicts_with = "password_file",
+        help = "Password of the encrypted entries (ZipCrypto; AES with the aes feature)."
+    )]
+    password: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Read the password of the encrypted entries from the first line of a file."
+    )]
+    password_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputCompressionArg::None,
+        help = "Compress the output stream (zstd needs the zstd feature)."
+    )]
+    output_compression: OutputCompressionArg,
+
+    #[arg(
+        long,
+        value_name = "KEY=NEW_KEY",
+        help = "Rename a key of the blob records, e.g. name=filename (repeatable; keys of the --layout)."
+    )]
+    field_map: Vec<FieldRename>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["external_dir", "tree_bodies"],
+        help = "Emit the metadata of the entries without their bodies (no body nor body_uri)."
+    )]
+    no_body: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Write progress lines (zips done, entries, bytes, rates) to stderr, in --log-format."
+    )]
+    progress: bool,
+
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 1.0,
+        requires = "progress",
+        help = "Seconds between two progress lines."
+    )]
+    progress_interval: f64,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Stop at the first skipped zip, entry or archive record, exiting with 1."
+    )]
+    strict: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["output_dir", "state_file"],
+        help = "Only check the zips (structure, CRC-32s, size limits), writing a JSON verdict per zip instead of blobs; exits with 1 unless all are ok."
+    )]
+    check: bool,
+
+    #[cfg(feature = "http-sink")]
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with_all = ["output_dir", "check"],
+        help = "POST each blob to this URL (JSON, the blob content_type and content_encoding in X-Blob-Content-Type/-Encoding headers) instead of writing it to stdout."
+    )]
+    post_url: Option<String>,
+
+    #[cfg(feature = "http-sink")]
+    #[arg(
+        long,
+        value_name = "NAME: VALUE",
+        value_parser = parse_header,
+        requires = "post_url",
+        help = "Add this header to the --post-url requests (repeatable; Content-Type stays application/json)."
+    )]
+    post_header: Vec<(String, String)>,
+
+    #[cfg(feature = "http-sink")]
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "post_url",
+        help = "Send the first line of this file as the bearer token of the --post-url requests."
+    )]
+    post_token_file: Option<PathBuf>,
+
+    #[cfg(feature = "http-sink")]
+    #[arg(
+        long,
+        default_value_t = 3,
+        requires = "post_url",
+        help = "Times a POST failing to connect or with a 429 or 5xx response is retried."
+    )]
+    post_retries: u32,
+
+    #[cfg(feature = "http-sink")]
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 0.5,
+        requires = "post_url",
+        help = "Seconds before the first retry of a POST, doubled for each retry after."
+    )]
+    post_backoff: f64,
+
+    #[cfg(feature = "http-sink")]
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 30.0,
+        requires = "post_url",
+        help = "Seconds before a POST times out (0: never)."
+    )]
+    post_timeout: f64,
+}
+
+fn parse_offset(s: &str) -> Result<FixedOffset, String> {
+    if s.eq_ignore_ascii_case("utc") || s == "Z" {
+        return Ok(FixedOffset::east_opt(0).ok_or("invalid offset")?);
+    }
+    s.parse::<FixedOffset>()
+        .map_err(|e| format!("expected UTC or an offset like +09:00: {}", e))
+}
+
+#[cfg(feature = "http-sink")]
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or("expected a header like 'Name: value'")?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("empty header name".into());
+    }
+    Ok((name.into(), value.trim().into()))
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ChecksumArg {
+    Crc32,
+    Sha256,
+}
+
+impl From<ChecksumArg> for Checksum {
+    fn from(arg: ChecksumArg) -> Self {
+        match arg {
+            ChecksumArg::Crc32 => Checksum::Crc32,
+            ChecksumArg::Sha256 => Checksum::Sha256,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BodyEncodingArg {
+    Base64,
+    Base64url,
+    Hex,
+    Utf8,
+}
+
+impl From<BodyEncodingArg> for BodyEncoding {
+    fn from(arg: BodyEncodingArg) -> Self {
+        match arg {
+            BodyEncodingArg::Base64 => BodyEncoding::Base64,
+            BodyEncodingArg::Base64url => BodyEncoding::Base64Url,
+            BodyEncodingArg::Hex => BodyEncoding::Hex,
+            BodyEncodingArg::Utf8 => BodyEncoding::Utf8,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LogFormatArg {
+    Text,
+    Json,
+}
+
+impl From<LogFormatArg> for LogFormat {
+    fn from(arg: LogFormatArg) -> Self {
+        match arg {
+            LogFormatArg::Text => LogFormat::Text,
+            LogFormatArg::Json => LogFormat::Json,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputFormatArg {
+    Ndjson,
+    JsonArray,
+    Pretty,
+}
+
+impl From<OutputFormatArg> for OutputFormat {
+    fn from(arg: OutputFormatArg) -> Self {
+        match arg {
+            OutputFormatArg::Ndjson => OutputFormat::Ndjson,
+            OutputFormatArg::JsonArray => OutputFormat::JsonArray,
+            OutputFormatArg::Pretty => OutputFormat::Pretty,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DetectContentTypeArg {
+    None,
+    Extension,
+    Sniff,
+}
+
+impl From<DetectContentTypeArg> for ContentTypeDetection {
+    fn from(arg: DetectContentTypeArg) -> Self {
+        match arg {
+            DetectContentTypeArg::None => ContentTypeDetection::None,
+            DetectContentTypeArg::Extension => ContentTypeDetection::Extension,
+            DetectContentTypeArg::Sniff => ContentTypeDetection::Sniff,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LayoutArg {
+    Nested,
+    Flat,
+}
+
+impl From<LayoutArg> for Layout {
+    fn from(arg: LayoutArg) -> Self {
+        match arg {
+            LayoutArg::Nested => Layout::Nested,
+            LayoutArg::Flat => Layout::Flat,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ArchiveRecordArg {
+    None,
+    Also,
+    Only,
+}
+
+impl From<ArchiveRecordArg> for ArchiveRecordMode {
+    fn from(arg: ArchiveRecordArg) -> Self {
+        match arg {
+            ArchiveRecordArg::None => ArchiveRecordMode::None,
+            ArchiveRecordArg::Also => ArchiveRecordMode::Also,
+            ArchiveRecordArg::Only => ArchiveRecordMode::Only,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EntryOrderArg {
+    None,
+    Name,
+    Mtime,
+    Size,
+}
+
+impl From<EntryOrderArg> for EntryOrder {
+    fn from(arg: EntryOrderArg) -> Self {
+        match arg {
+            EntryOrderArg::None => EntryOrder::Archive,
+            EntryOrderArg::Name => EntryOrder::Name,
+            EntryOrderArg::Mtime => EntryOrder::Mtime,
+            EntryOrderArg::Size => EntryOrder::Size,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FilenameEncodingArg {
+    Auto,
+    Utf8,
+    Cp437,
+    #[value(name = "shift_jis")]
+    ShiftJis,
+    Gbk,
+    Big5,
+    #[value(name = "euc_kr")]
+    EucKr,
+}
+
+impl From<FilenameEncodingArg> for FilenameEncoding {
+    fn from(arg: FilenameEncodingArg) -> Self {
+        match arg {
+            FilenameEncodingArg::Auto => FilenameEncoding::Auto,
+            FilenameEncodingArg::Utf8 => FilenameEncoding::Utf8,
+            FilenameEncodingArg::Cp437 => FilenameEncoding::Cp437,
+            FilenameEncodingArg::ShiftJis => FilenameEncoding::ShiftJis,
+            FilenameEncodingArg::Gbk => FilenameEncoding::Gbk,
+            FilenameEncodingArg::Big5 => FilenameEncoding::Big5,
+            FilenameEncodingArg::EucKr => FilenameEncoding::EucKr,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum InputOrderArg {
+    AsGiven,
+    SmallestFirst,
+    LargestFirst,
+    OldestFirst,
+}
+
+impl From<InputOrderArg> for InputOrder {
+    fn from(arg: InputOrderArg) -> Self {
+        match arg {
+            InputOrderArg::AsGiven => InputOrder::AsGiven,
+            InputOrderArg::SmallestFirst => InputOrder::SmallestFirst,
+            InputOrderArg::LargestFirst => InputOrder::LargestFirst,
+            InputOrderArg::OldestFirst => InputOrder::OldestFirst,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum InputFormatArg {
+    Lines,
+    Jsonl,
+    Bytes,
+    Entries,
+}
+
+impl From<InputFormatArg> for InputFormat {
+    fn from(arg: InputFormatArg) -> Self {
+        match arg {
+            InputFormatArg::Lines => InputFormat::Lines,
+            InputFormatArg::Jsonl => InputFormat::Jsonl,
+            InputFormatArg::Bytes => InputFormat::Bytes,
+            InputFormatArg::Entries => InputFormat::Entries,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputModeArg {
+    Flat,
+    Tree,
+}
+
+impl From<OutputModeArg> for OutputMode {
+    fn from(arg: OutputModeArg) -> Self {
+        match arg {
+            OutputModeArg::Flat => OutputMode::Flat,
+            OutputModeArg::Tree => OutputMode::Tree,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputCompressionArg {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl From<OutputCompressionArg> for OutputCompression {
+    fn from(arg: OutputCompressionArg) -> Self {
+        match arg {
+            OutputCompressionArg::None => OutputCompression::None,
+            OutputCompressionArg::Gzip => OutputCompression::Gzip,
+            OutputCompressionArg::Zstd => OutputCompression::Zstd,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OversizePolicyArg {
+    Skip,
+    Truncate,
+    Chunk,
+}
+
+impl From<OversizePolicyArg> for OversizePolicy {
+    fn from(arg: OversizePolicyArg) -> Self {
+        match arg {
+            OversizePolicyArg::Skip => OversizePolicy::Skip,
+            OversizePolicyArg::Truncate => OversizePolicy::Truncate,
+            OversizePolicyArg::Chunk => OversizePolicy::Chunk,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Validates JSON blobs read from stdin and reports the invalid ones.
+    Verify,
+}
+
+fn verify() {
+    match stdin2jsons2violations2stdout() {
+        Ok(0) => {}
+        Ok(invalid) => {
+            eprintln!("Error: {} invalid records", invalid);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to verify JSON blobs from stdin: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn check(cli: &Cli, options: &Options) {
+    let checked = match cli.root_dir.as_deref() {
+        Some(root) => dir2checks2writer(root, &cli.extension, options, io::stdout().lock())
+            .map_err(|e| format!("Failed to check zip files under {}: {}", root.display(), e)),
+        None => stdin2zfilenames2checks2writer(options, io::stdout().lock())
+            .map_err(|e| format!("Failed to check zip files from stdin: {}", e)),
+    };
+    match checked {
+        Ok(0) => {}
+        Ok(failed) => {
+            eprintln!("Error: {} zips failed the check", failed);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn convert<W: Write>(cli: &Cli, options: &Options, wtr: W) -> Result<RunStats, String> {
+    if let Some(manifest) = cli.manifest.as_deref() {
+        return manifest2zip2blobs2jsons2writer(manifest, options, wtr).map_err(|e| {
+            format!(
+                "Failed to process the entries listed in {}: {}",
+                manifest.display(),
+                e
+            )
+        });
+    }
+    match cli.root_dir.as_deref() {
+        Some(root) => dir2zip2blobs2jsons2writer(root, &cli.extension, options, wtr).map_err(|e| {
+            format!(
+                "Failed to process zip files under {}: {}",
+                root.display(),
+                e
+            )
+        }),
+        None => stdin2zfilenames2zip2blobs2jsons2writer(options, wtr)
+            .map_err(|e| format!("Failed to process zip files from stdin: {}", e)),
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
-    if let Err(e) = stdin2zfilenames2zip2blobs2jsons2stdout(
-        cli.zip_size_max,
-        &cli.item_content_type,
-        &cli.item_content_encoding,
-        cli.item_size_max,
-        cli.verbose,
-    ) {
-        eprintln!("Error: Failed to process zip files from stdin: {}", e);
+    if let Some(Command::Verify) = cli.command {
+        verify();
+        return;
+    }
+    let partitions = cli
+        .output_dir
+        .as_deref()
+        .filter(|_| !cli.partition_by.is_empty())
+        .map(|dir| {
+            PartitionedOutput::new(dir, cli.partition_by.clone())
+                .with_max_open(cli.partition_max_open)
+        });
+    let rotating_dir = cli.output_dir.as_deref().filter(|_| partitions.is_none());
+    if rotating_dir.is_some() && !matches!(cli.output_format, OutputFormatArg::Ndjson) {
+        eprintln!("Error: --output-dir without --partition-by only writes NDJSON");
         process::exit(1);
     }
+    if rotating_dir.is_some() && !matches!(cli.output_compression, OutputCompressionArg::None) {
+        eprintln!("Error: --output-compression only applies to stdout, not to --output-dir files");
+        process::exit(1);
+    }
+    let log_out: Box<dyn Write + Send> = match cli.log_file.as_deref() {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("Error: Failed to create {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        },
+        None => Box::new(io::stderr()),
+    };
+    let log = LogSink::new(cli.log_format.into(), log_out);
+    let progress = ProgressLog::new(
+        cli.log_format.into(),
+        Duration::try_from_secs_f64(cli.progress_interval).unwrap_or(Duration::ZERO),
+        Box::new(io::stderr()),
+    );
+    let field_map = match cli.field_map.as_slice() {
+        [] => None,
+        renames => match FieldMap::new(renames.to_vec()) {
+            Ok(map) => Some(map),
+            Err(e) => {
+                eprintln!("Error: Invalid --field-map: {}", e);
+                process::exit(1);
+            }
+        },
+    };
+    let password = match cli.password_file.as_deref() {
+        Some(path) => match fs::read(path) {
+            Ok(mut password) => {
+                // The first line only, so that a trailing newline is not part of it.
+                if let Some(end) = password.iter().position(|&b| b == b'\n') {
+                    password.truncate(end);
+                }
+                if password.last() == Some(&b'\r') {
+                    password.pop();
+                }
+                Some(password)
+            }
+            Err(e) => {
+                eprintln!("Error: Failed to read {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        },
+        None => cli.password.clone().map(String::into_bytes),
+    };
+    let checkpoint = cli
+        .state_file
+        .as_deref()
+        .map(|path| match Checkpoint::load(path) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                eprintln!("Error: Failed to read {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        });
+    #[cfg(feature = "http-sink")]
+    let http_sink = cli.post_url.as_deref().map(|url| {
+        let timeout = Duration::try_from_secs_f64(cli.post_timeout)
+            .ok()
+            .filter(|timeout| !timeout.is_zero());
+        let backoff = Duration::try_from_secs_f64(cli.post_backoff).unwrap_or(Duration::ZERO);
+        let mut sink = HttpSink::new(url, timeout).with_retries(cli.post_retries, backoff);
+        for (name, value) in &cli.post_header {
+            sink = sink.with_header(name, value);
+        }
+        if let Some(path) = cli.post_token_file.as_deref() {
+            match fs::read_to_string(path) {
+                Ok(token) => {
+                    let token = token.lines().next().unwrap_or_default();
+                    sink = sink.with_header("Authorization", &format!("Bearer {}", token));
+                }
+                Err(e) => {
+                    eprintln!("Error: Failed to read {}: {}", path.display(), e);
+                    process::exit(1);
+                }
+            }
+        }
+        sink
+    });
+    let output_limit = cli.max_total_output_bytes.map(OutputLimit::new);
+    let options = Options {
+        max_zip_size: cli.zip_size_max,
+        content_type: &cli.item_content_type,
+        content_encoding: &cli.item_content_encoding,
+        max_item_size: cli.item_size_max,
+        max_entries_per_zip: cli.max_entries_per_zip,
+        output_limit: output_limit.as_ref(),
+        verbose: cli.verbose || cli.log_file.is_some(),
+        ooxml: cli.ooxml,
+        ooxml_text: cli.ooxml_text,
+        jar_manifest: cli.jar_manifest,
+        text_stats: cli.text_stats,
+        prefetch: cli.prefetch,
+        ascii_only: cli.ascii_only,
+        external_dir: cli.external_dir.as_deref(),
+        external_threshold: cli.external_threshold,
+        output_mode: cli.output_mode.into(),
+        tree_bodies: cli.tree_bodies,
+        input_format: cli.input_format.into(),
+        order: cli.order.into(),
+        archive_record: cli.archive_record.into(),
+        layout: cli.layout.into(),
+        path_pattern: cli.path_pattern.as_ref(),
+        partitions: partitions.as_ref(),
+        #[cfg(feature = "http-sink")]
+        sink: http_sink.as_ref().map(|sink| sink as &dyn BlobSink),
+        #[cfg(not(feature = "http-sink"))]
+        sink: None,
+        streaming: cli.streaming,
+        include: &cli.include,
+        entry_names: None,
+        exclude: &cli.exclude,
+        jobs: cli.jobs,
+        detect_content_type: cli.detect_content_type.into(),
+        output_format: cli.output_format.into(),
+        body_encoding: cli.body_encoding.into(),
+        checksum: cli.checksum.into(),
+        diagnostics: Some(&log),
+        stats: None,
+        max_nesting_depth: if cli.recurse_zips { cli.max_depth } else { 0 },
+        extended_metadata: cli.extended_metadata,
+        offsets: cli.with_offsets,
+        dos_timezone: cli.dos_timezone,
+        password: password.as_deref(),
+        output_compression: cli.output_compression.into(),
+        field_map: field_map.as_ref(),
+        body_mode: if cli.no_body {
+            BodyMode::MetadataOnly
+        } else {
+            BodyMode::Inline
+        },
+        progress: if cli.progress { Some(&progress) } else { None },
+        error_policy: if cli.strict {
+            ErrorPolicy::Strict
+        } else {
+            ErrorPolicy::Lenient
+        },
+        oversize_policy: cli.oversize_policy.into(),
+        entry_order: cli.sort_entries.into(),
+        filename_encoding: cli.filename_encoding.into(),
+        transform: None,
+        checkpoint: checkpoint.as_ref(),
+        encoding_rules: &cli.encoding_rule,
+        strip_encoding_suffix: cli.strip_encoding_suffix,
+    };
+    if cli.check {
+        check(&cli, &options);
+        return;
+    }
+    let converted = match rotating_dir {
+        Some(dir) => convert(
+            &cli,
+            &options,
+            RotatingWriter::new(dir, cli.split_records, cli.split_bytes),
+        ),
+        None => convert(&cli, &options, io::stdout().lock()),
+    };
+    // Saved after a failed run too, so that the zips converted before it are kept.
+    if let (Some(path), Some(checkpoint)) = (cli.state_file.as_deref(), &checkpoint)
+        && let Err(e) = checkpoint.save(path)
+    {
+        eprintln!("Error: Failed to write {}: {}", path.display(), e);
+        process::exit(1);
+    }
+    match converted {
+        Ok(stats) => {
+            if cli.progress {
+                let _ = progress.write_line(&Progress::from(&stats));
+            }
+            if cli.summary {
+                match serde_json::to_string(&stats) {
+                    Ok(summary) => eprintln!("{}", summary),
+                    Err(e) => eprintln!("Error: Failed to write the summary: {}", e),
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,341 @@
+// Validation of the archives themselves, without converting them.
+
+use crate::{
+    ArchiveEntries, ArchiveFormat, ArchiveReader, Error, InputFormat, Options, ReadError,
+    STDIN_ZIP_NAME, TarEntries, ZipInput, decompress, detect_format, dir2zfilenames, external,
+    filename2buf, format, rdr2buf, rdr2zentries, rdr2zinputs, record2writer, stdin2filenames,
+};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+// Bytes of an entry held in memory at a time while its data is checked.
+const CHECK_CHUNK_LEN: usize = 1 << 16;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Ok,
+    /// The archive, or the data of one of its entries, does not read back.
+    Corrupt,
+    /// The archive exceeds `max_zip_size`, or one of its entries `max_item_size`.
+    Oversized,
+    /// Not a zip, tar or gzip.
+    Unsupported,
+    /// The file could not be read.
+    Unreadable,
+}
+
+/// What checking one archive found, e.g.
+/// `{"path":"a.zip","verdict":"corrupt","entries":3,...,"item":"b.txt","error":"..."}`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ZipCheck {
+    pub path: String,
+    pub verdict: Verdict,
+    /// Entries listed by the archive.
+    pub entries: u64,
+    pub oversized_entries: u64,
+    pub corrupt_entries: u64,
+    /// Entries whose data was not checked: encrypted without (the right) password,
+    /// or in a compression method that cannot be decoded.
+    pub unchecked_entries: u64,
+    /// The entry of `error`, if it is about one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    /// The first problem found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ZipCheck {
+    fn new(path: &str) -> Self {
+        ZipCheck {
+            path: path.into(),
+            verdict: Verdict::Ok,
+            entries: 0,
+            oversized_entries: 0,
+            corrupt_entries: 0,
+            unchecked_entries: 0,
+            item: None,
+            error: None,
+        }
+    }
+
+    fn failed(path: &str, verdict: Verdict, error: String) -> Self {
+        ZipCheck {
+            verdict,
+            error: Some(error),
+            ..ZipCheck::new(path)
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.verdict == Verdict::Ok
+    }
+
+    // Records a corrupt archive (`item: None`) or entry, keeping the first error.
+    fn corrupt(&mut self, error: String, item: Option<String>) {
+        if self.verdict != Verdict::Corrupt {
+            self.verdict = Verdict::Corrupt;
+            self.item = item;
+            self.error = Some(error);
+        }
+    }
+
+    // Oversized entries only decide the verdict of an archive otherwise sound.
+    fn finish(mut self) -> Self {
+        if self.verdict == Verdict::Ok && self.oversized_entries > 0 {
+            self.verdict = Verdict::Oversized;
+        }
+        self
+    }
+}
+
+// Reads the data of every entry, checked against its CRC-32 and size.
+fn check_entries(entries: &dyn ArchiveReader, options: &Options, check: &mut ZipCheck) {
+    for handle_result in entries.entries() {
+        let handle = match handle_result {
+            Ok(handle) => handle,
+            Err(e) => {
+                check.corrupt(e.to_string(), None);
+                return;
+            }
+        };
+        let meta = handle.metadata();
+        check.entries += 1;
+        if meta.uncompressed_size > options.max_item_size {
+            check.oversized_entries += 1;
+        }
+
+        // The read checks the data against the CRC-32 and size itself.
+        let read = handle.read_body_chunks(CHECK_CHUNK_LEN, options.password, &mut |_| Ok(()));
+        match read {
+            Ok(()) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::PermissionDenied | io::ErrorKind::Unsupported
+                ) =>
+            {
+                check.unchecked_entries += 1;
+            }
+            Err(e) => {
+                check.corrupt_entries += 1;
+                check.corrupt(e.to_string(), Some(meta.name));
+            }
+        }
+    }
+}
+
+/// Checks the archive `data` (a zip or tar, maybe gzip or zstd-compressed) named `zip_name`.
+pub fn buf2check(zip_name: &str, data: &[u8], options: &Options) -> ZipCheck {
+    let mut check = ZipCheck::new(zip_name);
+    match detect_format(data) {
+        ArchiveFormat::Tar => tar2check(data, options, &mut check),
+        ArchiveFormat::Unknown if format::is_tar_name(zip_name) => {
+            tar2check(data, options, &mut check)
+        }
+        format @ (ArchiveFormat::Gzip | ArchiveFormat::Zstd) => {
+            let mut decompressed = Vec::new();
+            let Some(decompressor) = decompress::decompressed_input(format, data) else {
+                return ZipCheck::failed(
+                    zip_name,
+                    Verdict::Unsupported,
+                    format!(
+                        "unsupported format: {} (built without the zstd feature)",
+                        format
+                    ),
+                );
+            };
+            match rdr2buf(decompressor, &mut decompressed, options.max_zip_size) {
+                Ok(()) => return buf2check(zip_name, &decompressed, options),
+                Err(ReadError::SizeLimitExceeded) => {
+                    return ZipCheck::failed(
+                        zip_name,
+                        Verdict::Oversized,
+                        size_limit_error(options),
+                    );
+                }
+                Err(ReadError::Io(e)) => check.corrupt(e.to_string(), None),
+            }
+        }
+        // Zips with a prefix (e.g. self-extracting) are still located by their end record.
+        ArchiveFormat::Zip | ArchiveFormat::Unknown => match crate::zip_entries(data, options) {
+            Ok(entries) => check_entries(&entries, options, &mut check),
+            Err(e) => check.corrupt(e.to_string(), None),
+        },
+        format => {
+            return ZipCheck::failed(
+                zip_name,
+                Verdict::Unsupported,
+                format!("unsupported format: {}", format),
+            );
+        }
+    }
+    check.finish()
+}
+
+fn tar2check(data: &[u8], options: &Options, check: &mut ZipCheck) {
+    match TarEntries::from_slice(data) {
+        Ok(entries) => check_entries(&entries, options, check),
+        Err(e) => check.corrupt(e.to_string(), None),
+    }
+}
+
+fn size_limit_error(options: &Options) -> String {
+    format!("larger than {} bytes", options.max_zip_size)
+}
+
+// Checks an input read into `buf` (or tells why it could not be read).
+fn loaded2check(
+    input: &ZipInput,
+    loaded: &Result<(), ReadError>,
+    buf: &[u8],
+    options: &Options,
+) -> ZipCheck {
+    let zip_name = input.path.as_str();
+    match loaded {
+        Ok(()) => {}
+        Err(ReadError::SizeLimitExceeded) => {
+            return ZipCheck::failed(zip_name, Verdict::Oversized, size_limit_error(options));
+        }
+        Err(ReadError::Io(e)) => {
+            return ZipCheck::failed(zip_name, Verdict::Unreadable, e.to_string());
+        }
+    }
+    if let Some(expected) = &input.sha256 {
+        let actual = external::sha256_hex(buf);
+        if !expected.eq_ignore_ascii_case(&actual) {
+            let error = format!("sha256 mismatch: expected {}, actual {}", expected, actual);
+            return ZipCheck::failed(zip_name, Verdict::Corrupt, error);
+        }
+    }
+    buf2check(zip_name, buf, options)
+}
+
+/// Checks the archive at `input.path`, all of its entries whatever `input.entries`.
+///
+/// With `options.streaming`, zips are read from the file one entry at a time, so
+/// `max_zip_size` does not limit them; other archives are read whole.
+pub fn zinput2check(input: &ZipInput, buf: &mut Vec<u8>, options: &Options) -> ZipCheck {
+    if options.streaming && input.sha256.is_none() {
+        match zfile2check_streaming(&input.path, options) {
+            Ok(Some(check)) => return check,
+            Ok(None) => {}
+            Err(e) => return ZipCheck::failed(&input.path, Verdict::Unreadable, e.to_string()),
+        }
+    }
+    let loaded = filename2buf(&input.path, buf, options.max_zip_size);
+    loaded2check(input, &loaded, buf, options)
+}
+
+// Checks the zip at `zip_name` from the file; `None` when it is not a zip.
+fn zfile2check_streaming(zip_name: &str, options: &Options) -> Result<Option<ZipCheck>, io::Error> {
+    let file = File::open(zip_name)?;
+    let mut head = Vec::with_capacity(format::HEAD_LEN);
+    (&file)
+        .take(format::HEAD_LEN as u64)
+        .read_to_end(&mut head)?;
+    if detect_format(&head) != ArchiveFormat::Zip {
+        return Ok(None);
+    }
+    let mut check = ZipCheck::new(zip_name);
+    match ArchiveEntries::from_file(file) {
+        Ok(entries) => {
+            let entries = entries.with_filename_encoding(options.filename_encoding);
+            check_entries(&entries, options, &mut check);
+        }
+        Err(e) => check.corrupt(e.to_string(), None),
+    }
+    Ok(Some(check.finish()))
+}
+
+/// Checks the archives of `zinputs` in the order given, writing one [`ZipCheck`] per line.
+///
+/// `jobs`, `prefetch`, `order` and the checkpoint do not apply. Returns the number of
+/// archives not [`Verdict::Ok`], inputs that could not be listed included.
+pub fn zinputs2checks2writer<I, W>(
+    zinputs: I,
+    buf: &mut Vec<u8>,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<u64, io::Error>
+where
+    I: Iterator<Item = Result<ZipInput, io::Error>>,
+    W: Write,
+{
+    let mut failed: u64 = 0;
+    for input_res in zinputs {
+        match input_res {
+            Ok(input) => {
+                let check = zinput2check(&input, buf, options);
+                if !check.is_ok() {
+                    failed += 1;
+                }
+                record2writer(&check, options, wtr)?;
+            }
+            Err(e) => {
+                failed += 1;
+                options.report_unrecoverable(e, None)?;
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// Checks the archives named on stdin (in `options.input_format`), writing to `wtr`.
+pub fn stdin2zfilenames2checks2writer<W>(options: &Options, wtr: W) -> Result<u64, Error>
+where
+    W: Write,
+{
+    let mut writer = BufWriter::new(wtr);
+    let mut buf: Vec<u8> = Vec::with_capacity((1 << 20) * 2);
+    let failed = match options.input_format {
+        InputFormat::Lines => zinputs2checks2writer(
+            stdin2filenames().map(|res| res.map(ZipInput::from)),
+            &mut buf,
+            options,
+            &mut writer,
+        )?,
+        InputFormat::Jsonl => zinputs2checks2writer(
+            rdr2zinputs(io::stdin().lock()),
+            &mut buf,
+            options,
+            &mut writer,
+        )?,
+        InputFormat::Entries => zinputs2checks2writer(
+            rdr2zentries(io::stdin().lock()),
+            &mut buf,
+            options,
+            &mut writer,
+        )?,
+        InputFormat::Bytes => {
+            let input = ZipInput::from(STDIN_ZIP_NAME.to_string());
+            let loaded = rdr2buf(io::stdin().lock(), &mut buf, options.max_zip_size);
+            let check = loaded2check(&input, &loaded, &buf, options);
+            record2writer(&check, options, &mut writer)?;
+            u64::from(!check.is_ok())
+        }
+    };
+    writer.flush()?;
+    Ok(failed)
+}
+
+/// Checks the archives found under `root` (see [`dir2zfilenames`]), writing to `wtr`.
+pub fn dir2checks2writer<W>(
+    root: &Path,
+    extensions: &[String],
+    options: &Options,
+    wtr: W,
+) -> Result<u64, Error>
+where
+    W: Write,
+{
+    let mut writer = BufWriter::new(wtr);
+    let mut buf: Vec<u8> = Vec::with_capacity((1 << 20) * 2);
+    let zinputs = dir2zfilenames(root, extensions).map(|res| res.map(ZipInput::from));
+    let failed = zinputs2checks2writer(zinputs, &mut buf, options, &mut writer)?;
+    writer.flush()?;
+    Ok(failed)
+}
@@ -0,0 +1,153 @@
+// The zips converted by earlier runs, to skip them while they are unchanged.
+
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Size and modification time of a zip, telling whether it changed since it was converted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    /// Nanoseconds since the Unix epoch.
+    pub mtime_ns: u64,
+}
+
+impl FileStamp {
+    pub fn of<P>(path: P) -> Result<Self, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let meta = fs::metadata(path)?;
+        let mtime = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(FileStamp {
+            size: meta.len(),
+            mtime_ns: u64::try_from(mtime.as_nanos()).unwrap_or(u64::MAX),
+        })
+    }
+}
+
+// One line of a checkpoint file.
+#[derive(Serialize, Deserialize)]
+struct CheckpointLine {
+    path: String,
+    #[serde(flatten)]
+    stamp: FileStamp,
+}
+
+/// Paths of the converted zips with their [`FileStamp`]s; shared by the worker threads.
+///
+/// Saved as JSON lines, e.g. `{"path":"a.zip","size":123,"mtime_ns":1700000000000000000}`.
+#[derive(Debug, Default)]
+pub struct Checkpoint {
+    done: Mutex<BTreeMap<String, FileStamp>>,
+    // Stamps of the zips being converted, taken before they were read.
+    started: Mutex<BTreeMap<String, FileStamp>>,
+}
+
+impl Checkpoint {
+    /// Reads a checkpoint file; a missing file is an empty checkpoint.
+    pub fn load<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Checkpoint::default()),
+            Err(e) => return Err(Error::Io(e)),
+        };
+        let mut done = BTreeMap::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed: CheckpointLine = serde_json::from_str(&line)?;
+            done.insert(parsed.path, parsed.stamp);
+        }
+        Ok(Checkpoint {
+            done: Mutex::new(done),
+            started: Mutex::default(),
+        })
+    }
+
+    /// Writes the checkpoint to `path`, replacing the file only once it is complete.
+    pub fn save<P>(&self, path: P) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let done = self
+            .done
+            .lock()
+            .map_err(|_| io::Error::other("checkpoint poisoned"))?;
+        let mut out = BufWriter::new(File::create(&partial)?);
+        for (path, stamp) in done.iter() {
+            let line = CheckpointLine {
+                path: path.clone(),
+                stamp: *stamp,
+            };
+            serde_json::to_writer(&mut out, &line)?;
+            writeln!(out)?;
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&partial, path)
+    }
+
+    /// Whether the zip at `path` was converted with this same stamp.
+    pub fn is_done(&self, path: &str, stamp: &FileStamp) -> bool {
+        self.done
+            .lock()
+            .is_ok_and(|done| done.get(path) == Some(stamp))
+    }
+
+    pub fn mark_done(&self, path: &str, stamp: FileStamp) {
+        if let Ok(mut done) = self.done.lock() {
+            done.insert(path.to_string(), stamp);
+        }
+    }
+
+    /// Number of the converted zips recorded.
+    pub fn len(&self) -> usize {
+        self.done.lock().map(|done| done.len()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Remembers the stamp of a zip about to be converted, for `finish`.
+    pub(crate) fn start(&self, path: &str, stamp: FileStamp) {
+        if let Ok(mut started) = self.started.lock() {
+            started.insert(path.to_string(), stamp);
+        }
+    }
+
+    // Forgets a zip not converted completely since its `start`, so that `finish` leaves it out.
+    pub(crate) fn abandon(&self, path: &str) {
+        if let Ok(mut started) = self.started.lock() {
+            started.remove(path);
+        }
+    }
+
+    // Records a zip converted since its `start`.
+    pub(crate) fn finish(&self, path: &str) {
+        let stamp = self
+            .started
+            .lock()
+            .ok()
+            .and_then(|mut started| started.remove(path));
+        if let Some(stamp) = stamp {
+            self.mark_done(path, stamp);
+        }
+    }
+}
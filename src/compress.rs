@@ -0,0 +1,129 @@
+// Compression of the output stream.
+
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputCompression {
+    #[default]
+    None,
+    Gzip,
+    /// Needs the `zstd` cargo feature.
+    Zstd,
+}
+
+// Data compressed into each zstd frame; the frames of a stream are decoded as one.
+#[cfg(feature = "zstd")]
+const ZSTD_FRAME_LEN: usize = 1 << 20;
+
+#[cfg(feature = "zstd")]
+struct ZstdFrames<W: Write> {
+    inner: W,
+    pending: Vec<u8>,
+}
+
+#[cfg(feature = "zstd")]
+impl<W: Write> ZstdFrames<W> {
+    fn write_frame(&mut self) -> Result<(), io::Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let frame = ruzstd::encoding::compress_to_vec(
+            self.pending.as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+        self.inner.write_all(&frame)?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<W: Write> Write for ZstdFrames<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(ZSTD_FRAME_LEN - self.pending.len());
+        self.pending.extend_from_slice(&buf[..n]);
+        if self.pending.len() >= ZSTD_FRAME_LEN {
+            self.write_frame()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_frame()?;
+        self.inner.flush()
+    }
+}
+
+enum Inner<W: Write> {
+    None(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdFrames<W>),
+}
+
+/// Compresses what is written to it into the inner writer.
+///
+/// `finish` must be called to write the end of the compressed stream.
+pub struct CompressedWriter<W: Write>(Inner<W>);
+
+impl<W: Write> CompressedWriter<W> {
+    /// Fails with `io::ErrorKind::Unsupported` for zstd without the `zstd` feature.
+    pub fn new(compression: OutputCompression, inner: W) -> Result<Self, io::Error> {
+        let inner = match compression {
+            OutputCompression::None => Inner::None(inner),
+            OutputCompression::Gzip => Inner::Gzip(flate2::write::GzEncoder::new(
+                inner,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "zstd")]
+            OutputCompression::Zstd => Inner::Zstd(ZstdFrames {
+                inner,
+                pending: Vec::new(),
+            }),
+            #[cfg(not(feature = "zstd"))]
+            OutputCompression::Zstd => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "zstd output is not supported by this build",
+                ));
+            }
+        };
+        Ok(CompressedWriter(inner))
+    }
+
+    /// Ends the compressed stream and returns the inner writer.
+    pub fn finish(self) -> Result<W, io::Error> {
+        match self.0 {
+            Inner::None(mut inner) => {
+                inner.flush()?;
+                Ok(inner)
+            }
+            Inner::Gzip(encoder) => encoder.finish(),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(mut frames) => {
+                frames.flush()?;
+                Ok(frames.inner)
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            Inner::None(inner) => inner.write(buf),
+            Inner::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(frames) => frames.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.0 {
+            Inner::None(inner) => inner.flush(),
+            Inner::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(frames) => frames.flush(),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Content-Encoding of the entries by name, e.g. `*.gz=gzip`.
+
+use crate::{Glob, GlobError};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingRuleError {
+    /// A rule without `=`.
+    MissingSeparator(String),
+    EmptyEncoding(String),
+    Glob(GlobError),
+}
+
+impl fmt::Display for EncodingRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingRuleError::MissingSeparator(spec) => {
+                write!(f, "expected glob=encoding: {:?}", spec)
+            }
+            EncodingRuleError::EmptyEncoding(spec) => write!(f, "empty encoding in {:?}", spec),
+            EncodingRuleError::Glob(e) => write!(f, "invalid glob: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EncodingRuleError {}
+
+/// One `glob=encoding` rule: the entries matching `glob` get `encoding` as their Content-Encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingRule {
+    pub glob: Glob,
+    pub encoding: String,
+}
+
+impl FromStr for EncodingRule {
+    type Err = EncodingRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Split at the last `=`, which a glob may hold but an encoding may not.
+        let (glob, encoding) = s
+            .rsplit_once('=')
+            .ok_or_else(|| EncodingRuleError::MissingSeparator(s.into()))?;
+        if encoding.is_empty() {
+            return Err(EncodingRuleError::EmptyEncoding(s.into()));
+        }
+        Ok(EncodingRule {
+            glob: glob.parse().map_err(EncodingRuleError::Glob)?,
+            encoding: encoding.into(),
+        })
+    }
+}
+
+impl EncodingRule {
+    /// `name` without the extension the rule matched, e.g. `a.json` for `a.json.gz` and `*.gz`.
+    ///
+    /// `None` when the glob does not end with the extension of `name`.
+    pub fn strip<'n>(&self, name: &'n str) -> Option<&'n str> {
+        let dot = name.rfind('.')?;
+        let (stem, extension) = name.split_at(dot);
+        let stripped = !stem.is_empty()
+            && !stem.ends_with('/')
+            && self.glob.literal_suffix().ends_with(extension);
+        stripped.then_some(stem)
+    }
+}
+
+/// The first of `rules` matching the entry `name`.
+pub fn matching<'r>(rules: &'r [EncodingRule], name: &str) -> Option<&'r EncodingRule> {
+    rules.iter().find(|rule| rule.glob.is_match(name))
+}
@@ -0,0 +1,186 @@
+// Decryption of encrypted entries: traditional PKWARE (ZipCrypto) and WinZip AES.
+
+use std::io;
+
+/// Id of the WinZip AES extra field.
+pub const AES_EXTRA_FIELD: u16 = 0x9901;
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+fn crc32_update(crc: u32, b: u8) -> u32 {
+    CRC_TABLE[((crc ^ u32::from(b)) & 0xff) as usize] ^ (crc >> 8)
+}
+
+fn wrong_password() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "incorrect password")
+}
+
+struct ZipCryptoKeys([u32; 3]);
+
+impl ZipCryptoKeys {
+    fn new(password: &[u8]) -> Self {
+        let mut keys = ZipCryptoKeys([0x1234_5678, 0x2345_6789, 0x3456_7890]);
+        for &b in password {
+            keys.update(b);
+        }
+        keys
+    }
+
+    fn update(&mut self, plain: u8) {
+        let [k0, k1, k2] = &mut self.0;
+        *k0 = crc32_update(*k0, plain);
+        *k1 = k1
+            .wrapping_add(*k0 & 0xff)
+            .wrapping_mul(134_775_813)
+            .wrapping_add(1);
+        *k2 = crc32_update(*k2, (*k1 >> 24) as u8);
+    }
+
+    fn decrypt(&mut self, cipher: u8) -> u8 {
+        let temp = (self.0[2] | 2) as u16;
+        let plain = cipher ^ (temp.wrapping_mul(temp ^ 1) >> 8) as u8;
+        self.update(plain);
+        plain
+    }
+}
+
+const ZIP_CRYPTO_HEADER_LEN: usize = 12;
+
+/// Decrypts `data`, a ZipCrypto entry, in place and returns the data after its header.
+///
+/// `check` is the byte the last header byte decrypts to with the right password
+/// (the high byte of the CRC-32, or of the DOS time with a data descriptor).
+pub fn zipcrypto_decrypt<'a>(
+    password: &[u8],
+    check: u8,
+    data: &'a mut [u8],
+) -> Result<&'a [u8], io::Error> {
+    if data.len() < ZIP_CRYPTO_HEADER_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "encrypted entry shorter than its header",
+        ));
+    }
+    let mut keys = ZipCryptoKeys::new(password);
+    for b in data.iter_mut() {
+        *b = keys.decrypt(*b);
+    }
+    if data[ZIP_CRYPTO_HEADER_LEN - 1] != check {
+        return Err(wrong_password());
+    }
+    Ok(&data[ZIP_CRYPTO_HEADER_LEN..])
+}
+
+/// Contents of the WinZip AES extra field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AesExtra {
+    /// 1 (AE-1) or 2 (AE-2, whose CRC-32 is not stored).
+    pub version: u16,
+    /// 1, 2 or 3 for 128, 192 or 256-bit keys.
+    pub strength: u8,
+    /// Compression method of the data once decrypted.
+    pub method: u16,
+}
+
+impl AesExtra {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        match data {
+            [v0, v1, b'A', b'E', strength, m0, m1, ..] => Some(AesExtra {
+                version: u16::from_le_bytes([*v0, *v1]),
+                strength: *strength,
+                method: u16::from_le_bytes([*m0, *m1]),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Checks and decrypts `data`, a WinZip AES entry (salt, password verifier, data, MAC).
+#[cfg(feature = "aes")]
+pub fn aes_decrypt(password: &[u8], aes: AesExtra, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    use aes::cipher::{KeyIvInit, StreamCipher};
+    use hmac::{Hmac, Mac};
+    use sha1::Sha1;
+
+    const VERIFIER_LEN: usize = 2;
+    const MAC_LEN: usize = 10;
+
+    let key_len = match aes.strength {
+        1 => 16,
+        2 => 24,
+        3 => 32,
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown AES strength {}", other),
+            ));
+        }
+    };
+    let salt_len = key_len / 2;
+    if data.len() < salt_len + VERIFIER_LEN + MAC_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "encrypted entry shorter than its salt and MAC",
+        ));
+    }
+    let (salt, rest) = data.split_at(salt_len);
+    let (verifier, rest) = rest.split_at(VERIFIER_LEN);
+    let (cipher_text, mac) = rest.split_at(rest.len() - MAC_LEN);
+
+    let mut derived = vec![0u8; key_len * 2 + VERIFIER_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, 1000, &mut derived);
+    let (enc_key, rest) = derived.split_at(key_len);
+    let (mac_key, expected_verifier) = rest.split_at(key_len);
+    if verifier != expected_verifier {
+        return Err(wrong_password());
+    }
+
+    let invalid =
+        |e: &dyn std::fmt::Display| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+    let mut hmac = Hmac::<Sha1>::new_from_slice(mac_key).map_err(|e| invalid(&e))?;
+    hmac.update(cipher_text);
+    let computed = hmac.finalize().into_bytes();
+    if computed.get(..MAC_LEN) != Some(mac) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "authentication code does not match",
+        ));
+    }
+
+    // The counter is little-endian and starts at 1.
+    let mut iv = [0u8; 16];
+    iv[0] = 1;
+    let mut plain = cipher_text.to_vec();
+    match key_len {
+        16 => ctr::Ctr128LE::<aes::Aes128>::new_from_slices(enc_key, &iv)
+            .map_err(|e| invalid(&e))?
+            .apply_keystream(&mut plain),
+        24 => ctr::Ctr128LE::<aes::Aes192>::new_from_slices(enc_key, &iv)
+            .map_err(|e| invalid(&e))?
+            .apply_keystream(&mut plain),
+        _ => ctr::Ctr128LE::<aes::Aes256>::new_from_slices(enc_key, &iv)
+            .map_err(|e| invalid(&e))?
+            .apply_keystream(&mut plain),
+    }
+    Ok(plain)
+}
@@ -0,0 +1,183 @@
+// Decoding of the entry data by compression method, and of compressed inputs.
+
+use crate::ArchiveFormat;
+use rawzip::CompressionMethod;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// A compression method this build cannot decode (possibly for lack of a cargo feature).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedMethod(pub u16);
+
+impl fmt::Display for UnsupportedMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "compression method {} ({}) is not supported by this build",
+            self.0,
+            method_name(self.0)
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedMethod {}
+
+/// Human-readable name of a compression method id.
+pub fn method_name(id: u16) -> &'static str {
+    match CompressionMethod::from(id) {
+        CompressionMethod::Store => "stored",
+        CompressionMethod::Deflate => "deflate",
+        CompressionMethod::Deflate64 => "deflate64",
+        CompressionMethod::Bzip2 => "bzip2",
+        CompressionMethod::Lzma => "lzma",
+        CompressionMethod::Zstd | CompressionMethod::ZstdDeprecated => "zstd",
+        CompressionMethod::Xz => "xz",
+        CompressionMethod::Ppmd => "ppmd",
+        CompressionMethod::Aes => "aes",
+        _ => "unknown",
+    }
+}
+
+// Reads at most `limit` bytes so that a lying size in the headers cannot exhaust memory.
+fn read_all<R: Read, W: Write>(rdr: R, limit: u64, out: &mut W) -> Result<(), io::Error> {
+    io::copy(&mut rdr.take(limit), out).map(|_| ())
+}
+
+// A writer refusing to take more than a limit, for the decoders writing their output.
+#[cfg(feature = "xz")]
+struct LimitedWriter<'a, W> {
+    out: &'a mut W,
+    written: u64,
+    limit: u64,
+}
+
+#[cfg(feature = "xz")]
+impl<W: Write> Write for LimitedWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let room = self.limit.saturating_sub(self.written);
+        let n = buf.len().min(usize::try_from(room).unwrap_or(usize::MAX));
+        if n == 0 && !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "decoded data exceeds the entry size",
+            ));
+        }
+        let n = self.out.write(&buf[..n])?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Writes the decoded data read from `src`, an entry compressed with `method`,
+/// to `out`, stopping after `limit` bytes.
+///
+/// Fails with `io::ErrorKind::Unsupported` wrapping an [`UnsupportedMethod`]
+/// for methods this build cannot decode.
+pub fn decompress<R: Read, W: Write>(
+    method: CompressionMethod,
+    src: R,
+    limit: u64,
+    out: &mut W,
+) -> Result<(), io::Error> {
+    match method {
+        CompressionMethod::Store => read_all(src, limit, out),
+        CompressionMethod::Deflate => read_all(flate2::read::DeflateDecoder::new(src), limit, out),
+        #[cfg(feature = "bzip2")]
+        CompressionMethod::Bzip2 => read_all(bzip2::read::BzDecoder::new(src), limit, out),
+        #[cfg(feature = "deflate64")]
+        CompressionMethod::Deflate64 => read_all(deflate64::Deflate64Decoder::new(src), limit, out),
+        #[cfg(feature = "xz")]
+        CompressionMethod::Xz => {
+            let mut limited = LimitedWriter {
+                out,
+                written: 0,
+                limit,
+            };
+            match lzma_rs::xz_decompress(&mut io::BufReader::new(src), &mut limited) {
+                Ok(()) => Ok(()),
+                // Stopped at the limit, like the other methods.
+                Err(_) if limited.written >= limit => Ok(()),
+                Err(lzma_rs::error::Error::IoError(e)) => Err(e),
+                Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            }
+        }
+        // Id 20, given to zstd before 93, is still written by some tools.
+        #[cfg(feature = "zstd")]
+        CompressionMethod::Zstd | CompressionMethod::ZstdDeprecated => {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(io::BufReader::new(src))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            read_all(decoder, limit, out)
+        }
+        other => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            UnsupportedMethod(other.as_id().as_u16()),
+        )),
+    }
+}
+
+/// Reads the data of `data`, an input compressed in `format` (gzip, or zstd with the
+/// `zstd` feature), as decompressed; `None` for the other formats.
+pub(crate) fn decompressed_input<'a>(
+    format: ArchiveFormat,
+    data: &'a [u8],
+) -> Option<Box<dyn Read + 'a>> {
+    match format {
+        ArchiveFormat::Gzip => Some(Box::new(flate2::read::MultiGzDecoder::new(data))),
+        #[cfg(feature = "zstd")]
+        ArchiveFormat::Zstd => Some(Box::new(ZstdFrames {
+            rest: data,
+            frame: None,
+        })),
+        _ => None,
+    }
+}
+
+// The frames of a zstd stream decoded one after another, skippable frames skipped.
+#[cfg(feature = "zstd")]
+struct ZstdFrames<'a> {
+    rest: &'a [u8],
+    frame: Option<ruzstd::decoding::StreamingDecoder<&'a [u8], ruzstd::decoding::FrameDecoder>>,
+}
+
+#[cfg(feature = "zstd")]
+impl Read for ZstdFrames<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        use ruzstd::decoding::errors::{FrameDecoderError, ReadFrameHeaderError};
+
+        // The magic number and length before the data of a skippable frame.
+        const SKIPPABLE_HEADER_LEN: usize = 8;
+
+        loop {
+            if let Some(frame) = &mut self.frame {
+                let n = frame.read(buf)?;
+                if n > 0 || buf.is_empty() {
+                    return Ok(n);
+                }
+                self.rest = frame.get_ref();
+                self.frame = None;
+            }
+            if self.rest.is_empty() {
+                return Ok(0);
+            }
+            match ruzstd::decoding::StreamingDecoder::new(self.rest) {
+                Ok(frame) => self.frame = Some(frame),
+                Err(FrameDecoderError::ReadFrameHeaderError(ReadFrameHeaderError::SkipFrame {
+                    length,
+                    ..
+                })) => {
+                    let end = usize::try_from(length)
+                        .ok()
+                        .and_then(|length| length.checked_add(SKIPPABLE_HEADER_LEN));
+                    self.rest = end.and_then(|end| self.rest.get(end..)).ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::UnexpectedEof, "truncated skippable frame")
+                    })?;
+                }
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// Warnings about skipped inputs, as text or JSON lines, or handed to the embedder.
+
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Warn,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Warn => write!(f, "warn"),
+        }
+    }
+}
+
+/// An event of the conversion, e.g. a zip or an entry that was skipped.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: Level,
+    /// What happened, e.g. `item_skipped`.
+    pub status: String,
+    /// Why, e.g. `size_limit_exceeded` or an error message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The zip concerned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The entry concerned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Compression method id of the entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Diagnostic {
+    /// A warning with `status` about the zip at `path`, if any; the other fields are empty.
+    pub fn warn(status: &str, path: Option<&str>) -> Self {
+        Diagnostic {
+            level: Level::Warn,
+            status: status.into(),
+            reason: None,
+            path: path.map(Into::into),
+            item: None,
+            size: None,
+            method: None,
+            format: None,
+            expected: None,
+            actual: None,
+            error: None,
+        }
+    }
+
+    /// Whether an input (a zip or the rest of it, an entry or an archive record) was left out
+    /// of the output.
+    pub fn is_skip(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "zip_skipped"
+                | "zip_processing_failed"
+                | "unrecoverable_error"
+                | "item_skipped"
+                | "archive_record_skipped"
+                | "zip_truncated"
+        )
+    }
+}
+
+/// Tab-separated `key:value` fields, e.g. `level:warn\tstatus:item_skipped\t...`.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "level:{}\tstatus:{}", self.level, self.status)?;
+        let fields = [
+            ("reason", self.reason.clone()),
+            ("path", self.path.clone()),
+            ("item", self.item.clone()),
+            ("size", self.size.map(|size| size.to_string())),
+            ("method", self.method.map(|method| method.to_string())),
+            ("format", self.format.clone()),
+            ("expected", self.expected.clone()),
+            ("actual", self.actual.clone()),
+            ("error", self.error.clone()),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                write!(f, "\t{}:{}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Receives the diagnostics of a conversion; shared by the worker threads.
+pub trait DiagnosticSink: Sync {
+    fn report(&self, diagnostic: &Diagnostic);
+}
+
+impl<F> DiagnosticSink for F
+where
+    F: Fn(&Diagnostic) + Sync,
+{
+    fn report(&self, diagnostic: &Diagnostic) {
+        self(diagnostic)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Tab-separated `key:value` lines.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Writes each diagnostic as a line to a writer, e.g. stderr or a log file.
+pub struct LogSink {
+    format: LogFormat,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl LogSink {
+    pub fn new(format: LogFormat, out: Box<dyn Write + Send>) -> Self {
+        LogSink {
+            format,
+            out: Mutex::new(out),
+        }
+    }
+
+    fn write_line(&self, diagnostic: &Diagnostic) -> Result<(), io::Error> {
+        let mut out = self
+            .out
+            .lock()
+            .map_err(|_| io::Error::other("log writer poisoned"))?;
+        match self.format {
+            LogFormat::Text => writeln!(out, "{}", diagnostic)?,
+            LogFormat::Json => {
+                serde_json::to_writer(&mut *out, diagnostic)?;
+                writeln!(out)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+impl DiagnosticSink for LogSink {
+    fn report(&self, diagnostic: &Diagnostic) {
+        // Losing a log line must not abort the conversion.
+        let _ = self.write_line(diagnostic);
+    }
+}
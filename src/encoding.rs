@@ -0,0 +1,87 @@
+// Transfer encodings of the blob bodies.
+
+use crate::external;
+use base64::{Engine as _, engine::general_purpose};
+
+/// How the body of a blob is written into `body`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyEncoding {
+    #[default]
+    Base64,
+    /// URL-safe alphabet, without padding.
+    Base64Url,
+    /// Lowercase hex.
+    Hex,
+    /// The body as is when it is valid UTF-8, base64 otherwise.
+    Utf8,
+}
+
+impl BodyEncoding {
+    /// Value of `content_transfer_encoding` for this encoding.
+    pub fn name(self) -> &'static str {
+        match self {
+            BodyEncoding::Base64 => "base64",
+            BodyEncoding::Base64Url => "base64url",
+            BodyEncoding::Hex => "hex",
+            BodyEncoding::Utf8 => "utf8",
+        }
+    }
+
+    /// Encodes `data`, returning the body and the encoding actually used.
+    pub fn encode(self, data: &[u8]) -> (String, BodyEncoding) {
+        let mut body = String::new();
+        let used = self.encode_into(data, &mut body);
+        (body, used)
+    }
+
+    /// Same as `encode`, replacing the contents of `body`, whose allocation is reused.
+    pub fn encode_into(self, data: &[u8], body: &mut String) -> BodyEncoding {
+        body.clear();
+        match self {
+            BodyEncoding::Base64 => general_purpose::STANDARD.encode_string(data, body),
+            BodyEncoding::Base64Url => general_purpose::URL_SAFE_NO_PAD.encode_string(data, body),
+            BodyEncoding::Hex => external::push_hex(data, body),
+            BodyEncoding::Utf8 => match std::str::from_utf8(data) {
+                Ok(text) => body.push_str(text),
+                Err(_) => return BodyEncoding::Base64.encode_into(data, body),
+            },
+        }
+        self
+    }
+}
+
+fn decode_hex(body: &str) -> Result<Vec<u8>, String> {
+    if !body.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".into());
+    }
+    body.as_bytes()
+        .chunks(2)
+        .map(|pair| match std::str::from_utf8(pair) {
+            Ok(digits) if pair.iter().all(u8::is_ascii_hexdigit) => {
+                u8::from_str_radix(digits, 16).map_err(|e| e.to_string())
+            }
+            _ => Err(format!(
+                "invalid hex digits: {}",
+                String::from_utf8_lossy(pair)
+            )),
+        })
+        .collect()
+}
+
+/// Decodes a body written with the `content_transfer_encoding` named `name`.
+///
+/// Returns `None` for unknown encodings.
+pub fn decode(name: &str, body: &str) -> Option<Result<Vec<u8>, String>> {
+    let decoded = match name {
+        "base64" => general_purpose::STANDARD
+            .decode(body)
+            .map_err(|e| e.to_string()),
+        "base64url" => general_purpose::URL_SAFE_NO_PAD
+            .decode(body)
+            .map_err(|e| e.to_string()),
+        "hex" => decode_hex(body),
+        "utf8" => Ok(body.as_bytes().to_vec()),
+        _ => return None,
+    };
+    Some(decoded)
+}
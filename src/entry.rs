@@ -0,0 +1,540 @@
+// Lazy access to the entries of a zip: metadata from the central directory,
+// data only when asked for.
+
+use crate::crypto::{self, AesExtra};
+use crate::filename::{self, FilenameEncoding};
+use crate::{decompress, zip_datetime_to_chrono_utc};
+use chrono::{DateTime, Utc};
+use rawzip::extra_fields::ExtraFieldId;
+use rawzip::time::ZipDateTimeKind;
+use rawzip::{
+    CompressionMethod, FileReader, RECOMMENDED_BUFFER_SIZE, ReaderAt, ZipArchive,
+    ZipArchiveEntryWayfinder, ZipFileHeaderRecord, ZipSliceArchive, ZipSliceEntries,
+};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::vec;
+
+/// Central-directory metadata of an entry; reading it does not touch the entry data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryMetadata {
+    pub name: String,
+    /// The bytes of the name when they could not be decoded; `name` then has replacement characters.
+    pub raw_name: Option<Vec<u8>>,
+    pub last_modified: DateTime<Utc>,
+    /// `last_modified` comes from the DOS fields, which have no time zone, and is read as UTC.
+    ///
+    /// Otherwise it comes from an extended timestamp (0x5455), NTFS (0x000a) or Unix extra field.
+    pub dos_time: bool,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    /// Compression method id (0: stored, 8: deflate, ...).
+    pub compression_method: u16,
+    pub crc32: u32,
+    pub is_dir: bool,
+    /// Unix file type and permission bits (derived from the DOS attributes for non-Unix creators).
+    pub mode: u32,
+}
+
+impl EntryMetadata {
+    fn from_header(
+        header: &ZipFileHeaderRecord<'_>,
+        name: String,
+        raw_name: Option<Vec<u8>>,
+    ) -> Self {
+        let last_modified = header.last_modified();
+        EntryMetadata {
+            name,
+            raw_name,
+            last_modified: zip_datetime_to_chrono_utc(&last_modified),
+            dos_time: matches!(last_modified, ZipDateTimeKind::Local(_)),
+            compressed_size: header.compressed_size_hint(),
+            uncompressed_size: header.uncompressed_size_hint(),
+            compression_method: header.compression_method().as_id().as_u16(),
+            crc32: header.crc32(),
+            is_dir: header.is_dir(),
+            mode: header.mode().value(),
+        }
+    }
+}
+
+enum Archive<'a> {
+    Slice(ZipSliceArchive<&'a [u8]>),
+    File(ZipArchive<FileReader>),
+}
+
+impl Archive<'_> {
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<(), io::Error> {
+        match self {
+            Archive::Slice(archive) => {
+                let data = usize::try_from(offset)
+                    .ok()
+                    .and_then(|start| archive.as_bytes().get(start..)?.get(..buf.len()))
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+                buf.copy_from_slice(data);
+                Ok(())
+            }
+            Archive::File(archive) => archive.get_ref().read_exact_at(buf, offset),
+        }
+    }
+}
+
+/// Fields of the central-directory record of an entry not parsed into [`EntryMetadata`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CentralRecord {
+    /// General purpose bit flags (bit 0: encrypted).
+    pub flags: u16,
+    pub external_attributes: u32,
+    pub comment: String,
+}
+
+// Size of the fixed part of a central-directory record.
+const CENTRAL_RECORD_LEN: usize = 46;
+
+// General purpose flag of encrypted entries.
+const FLAG_ENCRYPTED: u16 = 0x0001;
+
+// General purpose flag of the entries whose name and comment are UTF-8.
+const FLAG_UTF8: u16 = 0x0800;
+
+// Info-ZIP Unicode Path extra field: the UTF-8 name of an entry with a codepage name.
+const UNICODE_PATH_EXTRA_FIELD: u16 = 0x7075;
+
+fn le_u16(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+/// A zip whose entries are visited through [`EntryHandle`]s.
+///
+/// The zip is either held in memory (`from_slice`) or read from a file on
+/// demand (`from_file`), in which case only the entry being read is buffered.
+pub struct ArchiveEntries<'a> {
+    archive: Archive<'a>,
+    filename_encoding: FilenameEncoding,
+}
+
+impl<'a> ArchiveEntries<'a> {
+    pub fn from_slice(zipdata: &'a [u8]) -> Result<Self, io::Error> {
+        let archive = ZipArchive::from_slice(zipdata).map_err(io::Error::other)?;
+        Ok(ArchiveEntries {
+            archive: Archive::Slice(archive),
+            filename_encoding: FilenameEncoding::default(),
+        })
+    }
+
+    pub fn from_file(file: File) -> Result<Self, io::Error> {
+        let mut buf = vec![0u8; RECOMMENDED_BUFFER_SIZE];
+        let archive = ZipArchive::from_file(file, &mut buf).map_err(io::Error::other)?;
+        Ok(ArchiveEntries {
+            archive: Archive::File(archive),
+            filename_encoding: FilenameEncoding::default(),
+        })
+    }
+
+    /// Decodes the names of the entries without the UTF-8 flag in `encoding`.
+    pub fn with_filename_encoding(mut self, encoding: FilenameEncoding) -> Self {
+        self.filename_encoding = encoding;
+        self
+    }
+
+    /// The comment of the zip itself.
+    pub fn comment(&self) -> Result<String, io::Error> {
+        let comment = match &self.archive {
+            Archive::Slice(archive) => archive.comment().as_bytes().to_vec(),
+            Archive::File(archive) => {
+                let mut comment = Vec::new();
+                archive.comment().read_to_end(&mut comment)?;
+                comment
+            }
+        };
+        Ok(String::from_utf8_lossy(&comment).into_owned())
+    }
+
+    /// Iterates the central directory, yielding one handle per entry.
+    ///
+    /// The central directory of a file is read up front; the entry data is not.
+    pub fn handles(&self) -> Handles<'_> {
+        let encoding = self.filename_encoding;
+        let inner = match &self.archive {
+            Archive::Slice(archive) => {
+                HandlesInner::Slice(&self.archive, archive.entries(), encoding)
+            }
+            Archive::File(archive) => {
+                let mut buf = vec![0u8; RECOMMENDED_BUFFER_SIZE];
+                let mut entries = archive.entries(&mut buf);
+                let mut handles = Vec::new();
+                loop {
+                    match entries.next_entry() {
+                        Ok(Some(header)) => {
+                            handles.push(Ok(EntryHandle::new(&self.archive, &header, encoding)))
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            handles.push(Err(io::Error::other(e)));
+                            break;
+                        }
+                    }
+                }
+                HandlesInner::File(handles.into_iter())
+            }
+        };
+        Handles(inner)
+    }
+}
+
+enum HandlesInner<'a> {
+    Slice(&'a Archive<'a>, ZipSliceEntries<'a>, FilenameEncoding),
+    File(vec::IntoIter<Result<EntryHandle<'a>, io::Error>>),
+}
+
+/// Iterator returned by [`ArchiveEntries::handles`].
+pub struct Handles<'a>(HandlesInner<'a>);
+
+impl<'a> Iterator for Handles<'a> {
+    type Item = Result<EntryHandle<'a>, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            HandlesInner::Slice(archive, headers, encoding) => headers.next().map(|header_res| {
+                header_res
+                    .map(|header| EntryHandle::new(archive, &header, *encoding))
+                    .map_err(io::Error::other)
+            }),
+            HandlesInner::File(handles) => handles.next(),
+        }
+    }
+}
+
+// Decodes the name of an entry: from its Unicode Path extra field if it has a valid one,
+// otherwise in UTF-8 or `encoding` depending on its UTF-8 flag.
+fn entry_name(
+    archive: &Archive<'_>,
+    header: &ZipFileHeaderRecord<'_>,
+    encoding: FilenameEncoding,
+) -> (String, Option<Vec<u8>>) {
+    let raw = header.file_path().as_bytes();
+    // The same in all the encodings; the flags are not read.
+    if raw.is_ascii() {
+        return filename::decode_name(raw, true, encoding);
+    }
+    if let Some(name) = unicode_path(header, raw) {
+        return (name, None);
+    }
+    let mut flags = [0u8; 2];
+    let utf8_flag = archive
+        .read_exact_at(&mut flags, header.central_directory_offset() + 8)
+        .is_ok_and(|()| u16::from_le_bytes(flags) & FLAG_UTF8 != 0);
+    filename::decode_name(raw, utf8_flag, encoding)
+}
+
+// The name in the Unicode Path extra field, if it is for `raw` (the CRC-32 of the name it replaces).
+fn unicode_path(header: &ZipFileHeaderRecord<'_>, raw: &[u8]) -> Option<String> {
+    let (_, data) = header
+        .extra_fields()
+        .find(|(id, _)| *id == ExtraFieldId::new(UNICODE_PATH_EXTRA_FIELD))?;
+    let (&version, rest) = data.split_first()?;
+    let (crc, name) = rest.split_first_chunk::<4>()?;
+    if version != 1 || u32::from_le_bytes(*crc) != rawzip::crc32(raw) {
+        return None;
+    }
+    String::from_utf8(name.to_vec()).ok()
+}
+
+/// One entry of an [`ArchiveEntries`]; its data is located only by `read_body`.
+pub struct EntryHandle<'a> {
+    archive: &'a Archive<'a>,
+    wayfinder: ZipArchiveEntryWayfinder,
+    method: CompressionMethod,
+    meta: EntryMetadata,
+    central_offset: u64,
+    has_data_descriptor: bool,
+    aes: Option<AesExtra>,
+}
+
+impl<'a> EntryHandle<'a> {
+    fn new(
+        archive: &'a Archive<'a>,
+        header: &ZipFileHeaderRecord<'_>,
+        encoding: FilenameEncoding,
+    ) -> Self {
+        let (name, raw_name) = entry_name(archive, header, encoding);
+        EntryHandle {
+            archive,
+            wayfinder: header.wayfinder(),
+            method: header.compression_method(),
+            meta: EntryMetadata::from_header(header, name, raw_name),
+            central_offset: header.central_directory_offset(),
+            has_data_descriptor: header.has_data_descriptor(),
+            aes: header
+                .extra_fields()
+                .find(|(id, _)| *id == ExtraFieldId::new(crypto::AES_EXTRA_FIELD))
+                .and_then(|(_, data)| AesExtra::parse(data)),
+        }
+    }
+
+    fn central_fixed(&self) -> Result<[u8; CENTRAL_RECORD_LEN], io::Error> {
+        let mut fixed = [0u8; CENTRAL_RECORD_LEN];
+        self.archive
+            .read_exact_at(&mut fixed, self.central_offset)?;
+        Ok(fixed)
+    }
+
+    /// Reads the central-directory record of the entry again for the fields rawzip skips.
+    pub fn central_record(&self) -> Result<CentralRecord, io::Error> {
+        let fixed = self.central_fixed()?;
+        let name_len = u64::from(le_u16(&fixed, 28));
+        let extra_len = u64::from(le_u16(&fixed, 30));
+        let mut comment = vec![0u8; usize::from(le_u16(&fixed, 32))];
+        let comment_offset = self.central_offset + CENTRAL_RECORD_LEN as u64 + name_len + extra_len;
+        self.archive.read_exact_at(&mut comment, comment_offset)?;
+        Ok(CentralRecord {
+            flags: le_u16(&fixed, 8),
+            external_attributes: u32::from_le_bytes([fixed[38], fixed[39], fixed[40], fixed[41]]),
+            comment: String::from_utf8_lossy(&comment).into_owned(),
+        })
+    }
+
+    /// Offset in the zip of the stored data of the entry (compressed, maybe encrypted),
+    /// past its local header.
+    pub fn data_offset(&self) -> Result<u64, io::Error> {
+        let (start, _) = match self.archive {
+            Archive::Slice(archive) => archive
+                .get_entry(self.wayfinder)
+                .map_err(io::Error::other)?
+                .compressed_data_range(),
+            Archive::File(archive) => archive
+                .get_entry(self.wayfinder)
+                .map_err(io::Error::other)?
+                .compressed_data_range(),
+        };
+        Ok(start)
+    }
+
+    /// False for AES (AE-2) entries, whose CRC-32 is left out of the headers.
+    pub fn has_crc32(&self) -> bool {
+        !matches!(self.aes, Some(AesExtra { version: 2, .. }))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.meta.name
+    }
+
+    pub fn metadata(&self) -> EntryMetadata {
+        self.meta.clone()
+    }
+
+    /// Replaces the contents of `buf` with the decoded data of the entry.
+    ///
+    /// Decoded data is checked against the CRC-32 and size of the central directory.
+    /// Fails with `io::ErrorKind::Unsupported` when the compression method cannot be decoded.
+    pub fn read_body(&self, buf: &mut Vec<u8>) -> Result<(), io::Error> {
+        self.read_body_with_password(buf, None)
+    }
+
+    /// Same as `read_body`, decrypting ZipCrypto and (with the `aes` feature) AES entries
+    /// with `password`.
+    ///
+    /// Fails with `io::ErrorKind::PermissionDenied` when an entry is encrypted and the
+    /// password is missing or wrong.
+    pub fn read_body_with_password(
+        &self,
+        buf: &mut Vec<u8>,
+        password: Option<&[u8]>,
+    ) -> Result<(), io::Error> {
+        buf.clear();
+        let check = self.decode(password, self.size_limit(), buf)?;
+        self.check_decoded(check, rawzip::crc32(buf), buf.len() as u64)
+    }
+
+    /// Replaces the contents of `buf` with the first `len` decoded bytes of the entry,
+    /// which are not checked: the rest of the entry is not read.
+    pub fn read_body_prefix(
+        &self,
+        buf: &mut Vec<u8>,
+        len: u64,
+        password: Option<&[u8]>,
+    ) -> Result<(), io::Error> {
+        buf.clear();
+        self.decode(password, len, buf).map(|_| ())
+    }
+
+    /// Hands the decoded data of the entry to `on_chunk` in pieces of `chunk_len` bytes,
+    /// the last one maybe shorter, holding one piece in memory at a time.
+    ///
+    /// Checked like `read_body_with_password` once all the data is read; the pieces
+    /// already handed over when the check fails are not taken back.
+    pub fn read_body_chunks(
+        &self,
+        chunk_len: usize,
+        password: Option<&[u8]>,
+        on_chunk: &mut dyn FnMut(&[u8]) -> Result<(), io::Error>,
+    ) -> Result<(), io::Error> {
+        let chunk_len = chunk_len.max(1);
+        let mut chunks = Chunks {
+            chunk: Vec::with_capacity(chunk_len),
+            chunk_len,
+            crc: flate2::Crc::new(),
+            size: 0,
+            on_chunk,
+        };
+        let check = self.decode(password, self.size_limit(), &mut chunks)?;
+        if !chunks.chunk.is_empty() {
+            (chunks.on_chunk)(&chunks.chunk)?;
+        }
+        self.check_decoded(check, chunks.crc.sum(), chunks.size)
+    }
+
+    // One byte more than the declared size, for the size check to catch longer data.
+    fn size_limit(&self) -> u64 {
+        self.meta.uncompressed_size.saturating_add(1)
+    }
+
+    // Writes the decoded data of the entry to `out`, stopping after `limit` bytes.
+    fn decode<W: Write>(
+        &self,
+        password: Option<&[u8]>,
+        limit: u64,
+        out: &mut W,
+    ) -> Result<Check, io::Error> {
+        let fixed = self.central_fixed()?;
+        if le_u16(&fixed, 8) & FLAG_ENCRYPTED == 0 {
+            self.with_stored(|stored| decompress::decompress(self.method, stored, limit, out))?;
+            return Ok(Check::Crc);
+        }
+
+        let password = password.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "entry is encrypted and no password was given",
+            )
+        })?;
+        let mut stored = Vec::new();
+        self.with_stored(|rdr| {
+            decompress::decompress(
+                CompressionMethod::Store,
+                rdr,
+                self.meta.compressed_size,
+                &mut stored,
+            )
+        })?;
+        match self.aes {
+            Some(aes) => {
+                let plain = self.decrypt_aes(password, aes, &stored)?;
+                let method = CompressionMethod::from(aes.method);
+                decompress::decompress(method, plain.as_slice(), limit, out)?;
+            }
+            None if self.method == CompressionMethod::Aes => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "AES entry without its extra field",
+                ));
+            }
+            None => {
+                // The last header byte is the high byte of the CRC-32, or of the DOS
+                // time when the CRC-32 follows the data.
+                let check = if self.has_data_descriptor {
+                    fixed[13]
+                } else {
+                    (self.meta.crc32 >> 24) as u8
+                };
+                let plain = crypto::zipcrypto_decrypt(password, check, &mut stored)?;
+                decompress::decompress(self.method, plain, limit, out)?;
+            }
+        }
+        // AE-2 leaves the CRC-32 out, the MAC covering the data instead.
+        Ok(if self.has_crc32() {
+            Check::Crc
+        } else {
+            Check::Size
+        })
+    }
+
+    #[cfg(feature = "aes")]
+    fn decrypt_aes(
+        &self,
+        password: &[u8],
+        aes: AesExtra,
+        stored: &[u8],
+    ) -> Result<Vec<u8>, io::Error> {
+        crypto::aes_decrypt(password, aes, stored)
+    }
+
+    #[cfg(not(feature = "aes"))]
+    fn decrypt_aes(&self, _: &[u8], _: AesExtra, _: &[u8]) -> Result<Vec<u8>, io::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            decompress::UnsupportedMethod(self.meta.compression_method),
+        ))
+    }
+
+    // Passes a reader of the data as stored in the zip (compressed, maybe encrypted) to `f`.
+    fn with_stored<T, F>(&self, f: F) -> Result<T, io::Error>
+    where
+        F: FnOnce(&mut dyn Read) -> Result<T, io::Error>,
+    {
+        match self.archive {
+            Archive::Slice(archive) => {
+                let entry = archive
+                    .get_entry(self.wayfinder)
+                    .map_err(io::Error::other)?;
+                f(&mut entry.data())
+            }
+            Archive::File(archive) => {
+                let entry = archive
+                    .get_entry(self.wayfinder)
+                    .map_err(io::Error::other)?;
+                f(&mut entry.reader())
+            }
+        }
+    }
+
+    fn check_decoded(&self, check: Check, crc: u32, size: u64) -> Result<(), io::Error> {
+        let crc_ok = check != Check::Crc || crc == self.meta.crc32;
+        let size_ok = size == self.meta.uncompressed_size;
+        if !crc_ok || !size_ok {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "decoded data does not match: crc32 {:08x}, size {}",
+                    crc, size
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// What the decoded data of an entry is checked against.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Check {
+    Size,
+    /// The size and the CRC-32.
+    Crc,
+}
+
+// Cuts the decoded data into pieces for `EntryHandle::read_body_chunks`.
+struct Chunks<'f> {
+    chunk: Vec<u8>,
+    chunk_len: usize,
+    crc: flate2::Crc,
+    size: u64,
+    on_chunk: &'f mut dyn FnMut(&[u8]) -> Result<(), io::Error>,
+}
+
+impl Write for Chunks<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.chunk_len - self.chunk.len());
+        self.chunk.extend_from_slice(&buf[..n]);
+        self.crc.update(&buf[..n]);
+        self.size += n as u64;
+        if self.chunk.len() == self.chunk_len {
+            (self.on_chunk)(&self.chunk)?;
+            self.chunk.clear();
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
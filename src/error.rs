@@ -0,0 +1,113 @@
+// The error of the conversion functions.
+
+use crate::{Diagnostic, ReadError};
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A zip (or a zip nested in one) could not be parsed.
+    Zip(rawzip::Error),
+    Io(io::Error),
+    /// An input exceeded its size limit.
+    SizeLimit,
+    /// Bytes that are not UTF-8 where text is needed, e.g. a path.
+    Encoding(String),
+    /// A record could not be serialized, or an input line parsed, as JSON.
+    Serialize(serde_json::Error),
+    /// The run was stopped by `ErrorPolicy::Strict` at the first skipped input.
+    Aborted(Box<Diagnostic>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Zip(e) => write!(f, "invalid zip: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::SizeLimit => write!(f, "file size exceeds limit"),
+            Error::Encoding(what) => write!(f, "not UTF-8: {}", what),
+            Error::Serialize(e) => write!(f, "JSON error: {}", e),
+            Error::Aborted(diagnostic) => write!(f, "aborted (strict): {}", diagnostic),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Zip(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Serialize(e) => Some(e),
+            Error::SizeLimit | Error::Encoding(_) | Error::Aborted(_) => None,
+        }
+    }
+}
+
+// Wrapped into an `io::Error` by the functions returning one.
+fn is_wrapped(inner: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    inner.is::<Error>() || inner.is::<rawzip::Error>() || inner.is::<serde_json::Error>()
+}
+
+// Whether `err` carries an `Error::Aborted`, which is passed on rather than reported.
+pub(crate) fn is_aborted(err: &io::Error) -> bool {
+    err.get_ref()
+        .and_then(|inner| inner.downcast_ref::<Error>())
+        .is_some_and(|inner| matches!(inner, Error::Aborted(_)))
+}
+
+/// Unwraps the zip, JSON and crate errors carried by an `io::Error`.
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        if !err.get_ref().is_some_and(is_wrapped) {
+            return Error::Io(err);
+        }
+        let kind = err.kind();
+        let Some(inner) = err.into_inner() else {
+            return Error::Io(io::Error::from(kind));
+        };
+        let inner = match inner.downcast::<Error>() {
+            Ok(err) => return *err,
+            Err(inner) => inner,
+        };
+        let inner = match inner.downcast::<rawzip::Error>() {
+            Ok(err) => return Error::Zip(*err),
+            Err(inner) => inner,
+        };
+        match inner.downcast::<serde_json::Error>() {
+            Ok(err) => Error::Serialize(*err),
+            Err(inner) => Error::Io(io::Error::new(kind, inner)),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        match err {
+            Error::Io(e) => e,
+            // Not `InvalidData`, which the entry conversion handles as a bad entry.
+            aborted @ Error::Aborted(_) => io::Error::other(aborted),
+            other => io::Error::new(io::ErrorKind::InvalidData, other),
+        }
+    }
+}
+
+impl From<rawzip::Error> for Error {
+    fn from(err: rawzip::Error) -> Error {
+        Error::Zip(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Serialize(err)
+    }
+}
+
+impl From<ReadError> for Error {
+    fn from(err: ReadError) -> Error {
+        match err {
+            ReadError::Io(e) => Error::from(e),
+            ReadError::SizeLimitExceeded => Error::SizeLimit,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Content-addressed storage for bodies routed out of the JSON records.
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+pub fn to_hex(digest: &[u8]) -> String {
+    let mut hex = String::new();
+    push_hex(digest, &mut hex);
+    hex
+}
+
+/// Appends the lowercase hex digits of `data` to `hex`.
+pub fn push_hex(data: &[u8], hex: &mut String) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    hex.reserve(data.len() * 2);
+    for &b in data {
+        hex.push(char::from(DIGITS[usize::from(b >> 4)]));
+        hex.push(char::from(DIGITS[usize::from(b & 0x0f)]));
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// Digest of everything `rdr` yields, read in chunks.
+pub fn sha256_hex_reader<R: Read>(mut rdr: R) -> Result<String, io::Error> {
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0u8; 1 << 16];
+    loop {
+        match rdr.read(&mut chunk)? {
+            0 => return Ok(to_hex(&hasher.finalize())),
+            n => hasher.update(&chunk[..n]),
+        }
+    }
+}
+
+/// Path of the object for `digest`: `<root>/<digest[..2]>/<digest>`.
+pub fn object_path(root: &Path, digest: &str) -> PathBuf {
+    root.join(&digest[..2]).join(digest)
+}
+
+/// Stores `data` under its digest unless it is already present.
+///
+/// Returns the `file://` URI of the object.
+pub fn store(root: &Path, digest: &str, data: &[u8]) -> Result<String, io::Error> {
+    let path = std::path::absolute(object_path(root, digest))?;
+    if !path.exists() {
+        let dir = path.parent().unwrap_or(root);
+        fs::create_dir_all(dir)?;
+        // Write to a temporary name first so readers never see partial objects.
+        let tmp = dir.join(format!(".{}.tmp", digest));
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(data)?;
+        f.sync_all()?;
+        fs::rename(&tmp, &path)?;
+    }
+    Ok(format!("file://{}", path.to_string_lossy()))
+}
+
+/// Reads an object back from a `file://` URI.
+pub fn load(uri: &str) -> Result<Vec<u8>, io::Error> {
+    let path = uri.strip_prefix("file://").ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("unsupported body_uri: {}", uri),
+        )
+    })?;
+    fs::read(path)
+}
@@ -0,0 +1,105 @@
+// Renaming of the top-level keys of the blob records, e.g. `name=filename`.
+
+use crate::rewrite::{Rewrite, serialize_rewritten};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldMapError {
+    /// A rename without `=`.
+    MissingSeparator(String),
+    EmptyName(String),
+    /// A key renamed twice, or two keys renamed to the same name.
+    Duplicate(String),
+}
+
+impl fmt::Display for FieldMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMapError::MissingSeparator(spec) => {
+                write!(f, "expected key=new_key: {:?}", spec)
+            }
+            FieldMapError::EmptyName(spec) => write!(f, "empty key in {:?}", spec),
+            FieldMapError::Duplicate(key) => write!(f, "key mapped more than once: {}", key),
+        }
+    }
+}
+
+impl std::error::Error for FieldMapError {}
+
+/// One `key=new_key` rename.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldRename {
+    pub from: String,
+    pub to: String,
+}
+
+impl FromStr for FieldRename {
+    type Err = FieldMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once('=')
+            .ok_or_else(|| FieldMapError::MissingSeparator(s.into()))?;
+        if from.is_empty() || to.is_empty() {
+            return Err(FieldMapError::EmptyName(s.into()));
+        }
+        Ok(FieldRename {
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+}
+
+/// Renames of the top-level keys of the blob records.
+///
+/// Keys are those of the `Options::layout`, e.g. `metadata_ZipName` with the flat
+/// layout; keys not mapped are kept as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMap {
+    renames: Vec<FieldRename>,
+}
+
+impl FieldMap {
+    pub fn new(renames: Vec<FieldRename>) -> Result<Self, FieldMapError> {
+        for (i, rename) in renames.iter().enumerate() {
+            let earlier = &renames[..i];
+            if earlier.iter().any(|r| r.from == rename.from) {
+                return Err(FieldMapError::Duplicate(rename.from.clone()));
+            }
+            if earlier.iter().any(|r| r.to == rename.to) {
+                return Err(FieldMapError::Duplicate(rename.to.clone()));
+            }
+        }
+        Ok(FieldMap { renames })
+    }
+}
+
+/// Serializes a record with the keys renamed by a [`FieldMap`].
+pub struct MappedRecord<'a, T>(pub &'a FieldMap, pub &'a T);
+
+// The renames applied as the record is serialized; a renamed key replaces a field of the same name.
+impl Rewrite for FieldMap {
+    fn entry<M, V>(&self, map: &mut M, key: &str, value: &V) -> Result<(), M::Error>
+    where
+        M: SerializeMap,
+        V: Serialize + ?Sized,
+    {
+        match self.renames.iter().find(|rename| rename.from == key) {
+            Some(rename) => map.serialize_entry(&rename.to, value),
+            // Replaced by the renamed field.
+            None if self.renames.iter().any(|rename| rename.to == key) => Ok(()),
+            None => map.serialize_entry(key, value),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for MappedRecord<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_rewritten(self.1, serializer, self.0)
+    }
+}
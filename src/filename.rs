@@ -0,0 +1,154 @@
+// Decoding of the entry names, which zips store in a codepage unless flagged as UTF-8.
+
+use encoding_rs::{BIG5, EUC_KR, Encoding, GBK, SHIFT_JIS};
+
+/// Codepage of the names of the zip entries without the UTF-8 flag (general purpose bit 11).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilenameEncoding {
+    /// UTF-8 when the name is valid UTF-8, CP437 (the zip default) otherwise.
+    #[default]
+    Auto,
+    Utf8,
+    /// IBM PC codepage 437; every name decodes.
+    Cp437,
+    ShiftJis,
+    Gbk,
+    Big5,
+    EucKr,
+}
+
+impl FilenameEncoding {
+    /// Decodes `raw`, or `None` when it is not valid in this encoding.
+    pub fn decode(self, raw: &[u8]) -> Option<String> {
+        match self {
+            FilenameEncoding::Auto => FilenameEncoding::Utf8
+                .decode(raw)
+                .or_else(|| FilenameEncoding::Cp437.decode(raw)),
+            FilenameEncoding::Utf8 => String::from_utf8(raw.to_vec()).ok(),
+            FilenameEncoding::Cp437 => Some(raw.iter().map(|&b| cp437_char(b)).collect()),
+            FilenameEncoding::ShiftJis => decode_with(SHIFT_JIS, raw),
+            FilenameEncoding::Gbk => decode_with(GBK, raw),
+            FilenameEncoding::Big5 => decode_with(BIG5, raw),
+            FilenameEncoding::EucKr => decode_with(EUC_KR, raw),
+        }
+    }
+
+    // Decodes `raw`, replacing what is not valid in this encoding.
+    fn decode_lossy(self, raw: &[u8]) -> String {
+        match self {
+            FilenameEncoding::ShiftJis => SHIFT_JIS.decode_without_bom_handling(raw).0,
+            FilenameEncoding::Gbk => GBK.decode_without_bom_handling(raw).0,
+            FilenameEncoding::Big5 => BIG5.decode_without_bom_handling(raw).0,
+            FilenameEncoding::EucKr => EUC_KR.decode_without_bom_handling(raw).0,
+            FilenameEncoding::Auto | FilenameEncoding::Utf8 | FilenameEncoding::Cp437 => {
+                String::from_utf8_lossy(raw)
+            }
+        }
+        .into_owned()
+    }
+}
+
+fn decode_with(encoding: &'static Encoding, raw: &[u8]) -> Option<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(raw)
+        .map(|name| name.into_owned())
+}
+
+// The upper half of codepage 437; the lower half is ASCII.
+const CP437_HIGH: &str = concat!(
+    "ÇüéâäàåçêëèïîìÄÅ",
+    "ÉæÆôöòûùÿÖÜ¢£¥₧ƒ",
+    "áíóúñÑªº¿⌐¬½¼¡«»",
+    "░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+    "└┴┬├─┼╞╟╚╔╩╦╠═╬╧",
+    "╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "αßΓπΣσµτΦΘΩδ∞φε∩",
+    "≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}",
+);
+
+fn cp437_char(b: u8) -> char {
+    if b.is_ascii() {
+        return char::from(b);
+    }
+    CP437_HIGH
+        .chars()
+        .nth(usize::from(b - 0x80))
+        .unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// Decodes the name of an entry, in UTF-8 when `utf8_flag` is set and in `encoding` otherwise.
+///
+/// Returns the raw bytes too when they could not be decoded; the name then holds
+/// replacement characters.
+pub(crate) fn decode_name(
+    raw: &[u8],
+    utf8_flag: bool,
+    encoding: FilenameEncoding,
+) -> (String, Option<Vec<u8>>) {
+    let encoding = if utf8_flag {
+        FilenameEncoding::Utf8
+    } else {
+        encoding
+    };
+    match encoding.decode(raw) {
+        Some(name) => (name, None),
+        None => (encoding.decode_lossy(raw), Some(raw.to_vec())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cp437_has_a_character_for_each_high_byte() {
+        assert_eq!(CP437_HIGH.chars().count(), 128);
+        assert_eq!(cp437_char(0x41), 'A');
+        assert_eq!(cp437_char(0x80), 'Ç');
+        assert_eq!(cp437_char(0xE1), 'ß');
+        assert_eq!(cp437_char(0xFF), '\u{a0}');
+    }
+
+    #[test]
+    fn auto_falls_back_to_cp437() {
+        assert_eq!(
+            FilenameEncoding::Auto.decode("é.txt".as_bytes()),
+            Some("é.txt".into())
+        );
+        assert_eq!(
+            FilenameEncoding::Auto.decode(b"\x82t\xE9.txt"),
+            Some("étΘ.txt".into())
+        );
+    }
+
+    #[test]
+    fn undecodable_names_keep_their_raw_bytes() {
+        // A Shift-JIS lead byte without its trail byte.
+        let raw = b"a\x82.txt";
+        let (name, kept) = decode_name(raw, false, FilenameEncoding::ShiftJis);
+        assert_eq!(name, "a\u{fffd}.txt");
+        assert_eq!(kept, Some(raw.to_vec()));
+
+        let (name, kept) = decode_name("日本.txt".as_bytes(), false, FilenameEncoding::Utf8);
+        assert_eq!(name, "日本.txt");
+        assert_eq!(kept, None);
+    }
+
+    #[test]
+    fn the_utf8_flag_overrides_the_encoding() {
+        let raw = "é".as_bytes();
+        assert_eq!(
+            decode_name(raw, true, FilenameEncoding::ShiftJis),
+            ("é".into(), None)
+        );
+        // The same bytes as half-width katakana.
+        assert_eq!(
+            decode_name(raw, false, FilenameEncoding::ShiftJis),
+            ("\u{ff83}\u{ff69}".into(), None)
+        );
+        assert_eq!(
+            decode_name(raw, false, FilenameEncoding::Cp437),
+            ("├⌐".into(), None)
+        );
+    }
+}
@@ -0,0 +1,98 @@
+// Archive format detection by magic bytes (or, for tars, by extension).
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Gzip,
+    Zstd,
+    Tar,
+    SevenZip,
+    Unknown,
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Gzip => "gzip",
+            ArchiveFormat::Zstd => "zstd",
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::SevenZip => "7z",
+            ArchiveFormat::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+const ZIP_MAGICS: &[&[u8]] = &[b"PK\x03\x04", b"PK\x05\x06", b"PK\x07\x08"];
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const SEVEN_ZIP_MAGIC: &[u8] = &[0x37, 0x7a, 0xbc, 0xaf, 0x27, 0x1c];
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+
+/// Number of leading bytes `detect_format` looks at.
+pub const HEAD_LEN: usize = 512;
+
+/// Detects the format of an input from its first bytes.
+pub fn detect_format(head: &[u8]) -> ArchiveFormat {
+    if ZIP_MAGICS.iter().any(|m| head.starts_with(m)) {
+        return ArchiveFormat::Zip;
+    }
+    if head.starts_with(GZIP_MAGIC) {
+        return ArchiveFormat::Gzip;
+    }
+    if head.starts_with(ZSTD_MAGIC) {
+        return ArchiveFormat::Zstd;
+    }
+    if head.starts_with(SEVEN_ZIP_MAGIC) {
+        return ArchiveFormat::SevenZip;
+    }
+    if head.get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()) == Some(TAR_MAGIC) {
+        return ArchiveFormat::Tar;
+    }
+    ArchiveFormat::Unknown
+}
+
+/// Whether `name` has a tar extension (`.tar`, `.tar.gz`, `.tgz`, `.tar.zst`, `.tzst`),
+/// for the tars without the ustar magic.
+pub fn is_tar_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    [".tar", ".tar.gz", ".tgz", ".tar.zst", ".tzst"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tar_names_end_with_a_tar_extension() {
+        for name in [
+            "a.tar",
+            "dir/a.tar.gz",
+            "A.TGZ",
+            "a.Tar.Gz",
+            "a.tar.zst",
+            "a.tzst",
+        ] {
+            assert!(is_tar_name(name), "{}", name);
+        }
+        for name in ["a.zip", "a.gz", "tar", "a.tar.bz2", "a.tarx"] {
+            assert!(!is_tar_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn tars_are_detected_by_the_ustar_magic() {
+        let mut head = vec![0u8; HEAD_LEN];
+        assert_eq!(detect_format(&head), ArchiveFormat::Unknown);
+        head[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()].copy_from_slice(TAR_MAGIC);
+        assert_eq!(detect_format(&head), ArchiveFormat::Tar);
+        // Too short to hold the magic.
+        assert_eq!(detect_format(&head[..260]), ArchiveFormat::Unknown);
+    }
+}
@@ -0,0 +1,270 @@
+// Glob patterns selecting the entries of a zip by name.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`: any character but `/`.
+    AnyChar,
+    /// `*`: any run of characters without `/`.
+    Star,
+    /// `**/`: nothing, or any run of characters ending with `/`.
+    Dirs,
+    /// `**` elsewhere: any run of characters.
+    AnyPath,
+    /// `[a-z0-9]`, or `[!...]` when negated.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlobError {
+    UnclosedClass,
+    EmptyClass,
+    TrailingEscape,
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlobError::UnclosedClass => write!(f, "unclosed '['"),
+            GlobError::EmptyClass => write!(f, "empty character class"),
+            GlobError::TrailingEscape => write!(f, "trailing '\\'"),
+        }
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+/// A glob on entry names.
+///
+/// A pattern without `/` is matched against the last path component
+/// (`*.xml` selects `a/b.xml`); any other pattern against the whole name.
+/// Supports `?`, `*`, `**`, `[...]`, `[!...]` and `\` escapes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+    basename_only: bool,
+}
+
+fn parse_class(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<Token, GlobError> {
+    let negated = matches!(chars.peek(), Some('!') | Some('^'));
+    if negated {
+        chars.next();
+    }
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let c = match chars.next() {
+            None => return Err(GlobError::UnclosedClass),
+            // A `]` right after `[` is a member, as in POSIX.
+            Some(']') if !first => break,
+            Some('\\') => chars.next().ok_or(GlobError::TrailingEscape)?,
+            Some(c) => c,
+        };
+        first = false;
+        let mut lookahead = chars.clone();
+        match (lookahead.next(), lookahead.next()) {
+            (Some('-'), Some(end)) if end != ']' => {
+                chars.next();
+                chars.next();
+                ranges.push((c, end));
+            }
+            _ => ranges.push((c, c)),
+        }
+    }
+    if ranges.is_empty() {
+        return Err(GlobError::EmptyClass);
+    }
+    Ok(Token::Class { negated, ranges })
+}
+
+impl FromStr for Glob {
+    type Err = GlobError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Vec::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '?' => Token::AnyChar,
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        Token::Dirs
+                    } else {
+                        Token::AnyPath
+                    }
+                }
+                '*' => Token::Star,
+                '[' => parse_class(&mut chars)?,
+                '\\' => Token::Char(chars.next().ok_or(GlobError::TrailingEscape)?),
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        Ok(Glob {
+            basename_only: !s.contains('/'),
+            tokens,
+        })
+    }
+}
+
+impl Glob {
+    pub fn is_match(&self, name: &str) -> bool {
+        let subject = if self.basename_only {
+            name.trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or(name)
+        } else {
+            name
+        };
+        match_tokens(&self.tokens, subject)
+    }
+
+    /// The characters every match ends with, e.g. `.gz` for `*.gz`.
+    pub fn literal_suffix(&self) -> String {
+        let mut suffix: Vec<char> = self
+            .tokens
+            .iter()
+            .rev()
+            .map_while(|token| match token {
+                Token::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+        suffix.reverse();
+        suffix.into_iter().collect()
+    }
+}
+
+fn match_tokens(tokens: &[Token], name: &str) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return name.is_empty();
+    };
+    // Byte offsets just past each character of `name`, with 0 for the empty prefix.
+    let ends = || std::iter::once(0).chain(name.char_indices().map(|(ix, c)| ix + c.len_utf8()));
+    match token {
+        Token::Star => ends()
+            .take_while(|&end| !name[..end].contains('/'))
+            .any(|end| match_tokens(rest, &name[end..])),
+        Token::AnyPath => ends().any(|end| match_tokens(rest, &name[end..])),
+        Token::Dirs => ends()
+            .filter(|&end| end == 0 || name[..end].ends_with('/'))
+            .any(|end| match_tokens(rest, &name[end..])),
+        single => {
+            let mut chars = name.chars();
+            let Some(c) = chars.next() else {
+                return false;
+            };
+            let matched = match single {
+                Token::Char(expected) => c == *expected,
+                Token::AnyChar => c != '/',
+                Token::Class { negated, ranges } => {
+                    c != '/' && ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+                }
+                Token::Star | Token::AnyPath | Token::Dirs => false,
+            };
+            matched && match_tokens(rest, chars.as_str())
+        }
+    }
+}
+
+/// Whether an entry passes the include (any, when given) and exclude (none) globs.
+pub fn is_selected(name: &str, include: &[Glob], exclude: &[Glob]) -> bool {
+    (include.is_empty() || include.iter().any(|glob| glob.is_match(name)))
+        && !exclude.iter().any(|glob| glob.is_match(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str) -> Result<Glob, GlobError> {
+        pattern.parse()
+    }
+
+    #[test]
+    fn patterns_without_slash_match_the_basename() -> Result<(), GlobError> {
+        let xml = glob("*.xml")?;
+        assert!(xml.is_match("b.xml"));
+        assert!(xml.is_match("a/b.xml"));
+        assert!(xml.is_match("a/c/b.xml"));
+        assert!(!xml.is_match("a/b.xml.gz"));
+        // With a `/`, the whole name.
+        assert!(!glob("a/*.xml")?.is_match("x/a/b.xml"));
+        Ok(())
+    }
+
+    #[test]
+    fn star_does_not_cross_slashes() -> Result<(), GlobError> {
+        let star = glob("a/*.txt")?;
+        assert!(star.is_match("a/b.txt"));
+        assert!(star.is_match("a/.txt"));
+        assert!(!star.is_match("a/b/c.txt"));
+        assert!(glob("a/**.txt")?.is_match("a/b/c.txt"));
+        assert!(!glob("a?b")?.is_match("a/b"));
+        Ok(())
+    }
+
+    #[test]
+    fn double_star_slash_matches_any_directories() -> Result<(), GlobError> {
+        let dirs = glob("a/**/b")?;
+        assert!(dirs.is_match("a/b"));
+        assert!(dirs.is_match("a/x/b"));
+        assert!(dirs.is_match("a/x/y/b"));
+        assert!(!dirs.is_match("a/xb"));
+        assert!(!dirs.is_match("a/x/b/c"));
+        Ok(())
+    }
+
+    #[test]
+    fn classes_match_their_ranges_or_the_rest() -> Result<(), GlobError> {
+        let negated = glob("[!a-z]")?;
+        assert!(negated.is_match("A"));
+        assert!(negated.is_match("0"));
+        assert!(!negated.is_match("q"));
+        assert!(!negated.is_match("/"));
+        assert_eq!(glob("[^a-z]")?, negated);
+        assert!(glob("[a-c0]")?.is_match("0"));
+        // A trailing `-` is a member.
+        assert!(glob("[a-]")?.is_match("-"));
+        Ok(())
+    }
+
+    #[test]
+    fn closing_bracket_first_is_a_member() -> Result<(), GlobError> {
+        let bracket = glob("[]]")?;
+        assert!(bracket.is_match("]"));
+        assert!(!bracket.is_match("a"));
+        let negated = glob("[!]a]")?;
+        assert!(negated.is_match("b"));
+        assert!(!negated.is_match("]"));
+        assert_eq!(glob("[]"), Err(GlobError::UnclosedClass));
+        Ok(())
+    }
+
+    #[test]
+    fn escapes_match_literally() -> Result<(), GlobError> {
+        assert!(glob(r"\*.txt")?.is_match("*.txt"));
+        assert!(!glob(r"\*.txt")?.is_match("a.txt"));
+        assert!(glob(r"[\]]")?.is_match("]"));
+        assert_eq!(glob(r"a\"), Err(GlobError::TrailingEscape));
+        assert_eq!(glob(r"[a\"), Err(GlobError::TrailingEscape));
+        assert_eq!(glob("[a-z"), Err(GlobError::UnclosedClass));
+        Ok(())
+    }
+
+    #[test]
+    fn literal_suffix_stops_at_the_last_wildcard() -> Result<(), GlobError> {
+        assert_eq!(glob("*.json.gz")?.literal_suffix(), ".json.gz");
+        assert_eq!(glob("a/*")?.literal_suffix(), "");
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+// Uploads of the blob records to an HTTP ingestion endpoint, one POST per blob.
+
+use crate::{Blob, BlobSink};
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// Header carrying the `content_type` of the blob, the request itself being JSON.
+pub const BLOB_CONTENT_TYPE_HEADER: &str = "X-Blob-Content-Type";
+
+/// Header carrying the `content_encoding` of the blob.
+pub const BLOB_CONTENT_ENCODING_HEADER: &str = "X-Blob-Content-Encoding";
+
+/// POSTs each blob record to `url` as `application/json`, with the content type and
+/// encoding of the blob in [`BLOB_CONTENT_TYPE_HEADER`] and [`BLOB_CONTENT_ENCODING_HEADER`].
+///
+/// Connection errors, 429 and 5xx responses are retried with exponential backoff;
+/// other responses but 2xx fail the blob at once.
+pub struct HttpSink {
+    agent: ureq::Agent,
+    url: String,
+    headers: Vec<(String, String)>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl HttpSink {
+    /// A sink retrying 3 times, waiting 500ms before the first retry, with requests
+    /// timing out after `timeout` (`None`: never).
+    pub fn new(url: &str, timeout: Option<Duration>) -> Self {
+        let config = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(timeout)
+            .build();
+        HttpSink {
+            agent: ureq::Agent::new_with_config(config),
+            url: url.into(),
+            headers: Vec::new(),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+
+    /// Adds a header to every request, e.g. `Authorization`; a `Content-Type` is ignored,
+    /// the body always being the JSON record.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Retries a failed request `retries` times, waiting `backoff`, then twice as long
+    /// for each retry after.
+    pub fn with_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    // Sends one request; `Ok(false)` when it failed in a way worth retrying.
+    fn post(&self, blob: &Blob, record: &[u8]) -> Result<bool, io::Error> {
+        let mut request = self
+            .agent
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header(BLOB_CONTENT_TYPE_HEADER, blob.content_type.as_ref())
+            .header(BLOB_CONTENT_ENCODING_HEADER, blob.content_encoding.as_ref());
+        let headers = self
+            .headers
+            .iter()
+            .filter(|(name, _)| !name.eq_ignore_ascii_case("Content-Type"));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let status = match request.send(record) {
+            Ok(response) => response.status(),
+            Err(ureq::Error::Io(_) | ureq::Error::Timeout(_) | ureq::Error::HostNotFound) => {
+                return Ok(false);
+            }
+            Err(e) => return Err(e.into_io()),
+        };
+        if status.is_success() {
+            return Ok(true);
+        }
+        if status.is_server_error() || status.as_u16() == 429 {
+            return Ok(false);
+        }
+        Err(io::Error::other(format!(
+            "POST {} of {} returned {}",
+            self.url, blob.name, status
+        )))
+    }
+}
+
+impl BlobSink for HttpSink {
+    fn send(&self, blob: &Blob, record: &[u8]) -> Result<(), io::Error> {
+        let mut backoff = self.backoff;
+        for _ in 0..self.retries {
+            if self.post(blob, record)? {
+                return Ok(());
+            }
+            thread::sleep(backoff);
+            backoff = backoff.saturating_mul(2);
+        }
+        if self.post(blob, record)? {
+            return Ok(());
+        }
+        Err(io::Error::other(format!(
+            "POST {} of {} failed after {} retries",
+            self.url, blob.name, self.retries
+        )))
+    }
+}
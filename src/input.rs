@@ -0,0 +1,146 @@
+// Input specifications: plain filename lines, JSON job-spec lines or entry lists.
+
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::io::{self, BufRead};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputFormat {
+    /// One zip filename per line.
+    #[default]
+    Lines,
+    /// One JSON object per line, e.g. `{"path": "a.zip", "sha256": "..."}`.
+    Jsonl,
+    /// The bytes of a single zip archive, named `-` in the records.
+    Bytes,
+    /// One `zip path<TAB>entry name` per line, converting only the entries listed
+    /// (a line without a tab converts the whole zip); the consecutive lines of a zip make one input.
+    Entries,
+}
+
+/// A zip to convert.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ZipInput {
+    pub path: String,
+    /// Expected hex SHA-256 of the zip; the zip is not converted on mismatch.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Names of the only entries to convert, e.g. `dir/a.txt` (all when `None`); a zip
+    /// listing entries is read from the file as with `Options::streaming`.
+    #[serde(default)]
+    pub entries: Option<Vec<String>>,
+}
+
+impl From<String> for ZipInput {
+    fn from(path: String) -> Self {
+        Self {
+            path,
+            sha256: None,
+            entries: None,
+        }
+    }
+}
+
+pub fn rdr2zinputs<R>(rdr: R) -> impl Iterator<Item = Result<ZipInput, io::Error>>
+where
+    R: BufRead,
+{
+    rdr.lines()
+        .filter(|line_res| !matches!(line_res, Ok(line) if line.trim().is_empty()))
+        .map(|line_res| {
+            let line = line_res?;
+            serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+}
+
+// Adds an `InputFormat::Entries` line to the `pending` input, returning the input it
+// completes when the line is about another zip.
+pub(crate) fn push_entry_line(pending: &mut Option<ZipInput>, line: &str) -> Option<ZipInput> {
+    let (path, entry) = match line.split_once('\t') {
+        Some((path, entry)) => (path, Some(entry)),
+        None => (line, None),
+    };
+    if let Some(input) = pending.as_mut()
+        && input.path == path
+    {
+        match (&mut input.entries, entry) {
+            (Some(entries), Some(entry)) => entries.push(entry.into()),
+            // A line for the whole zip wins over the entries listed.
+            _ => input.entries = None,
+        }
+        return None;
+    }
+    pending.replace(ZipInput {
+        path: path.into(),
+        sha256: None,
+        entries: entry.map(|entry| vec![entry.into()]),
+    })
+}
+
+/// The inputs of [`InputFormat::Entries`] lines.
+pub fn rdr2zentries<R>(rdr: R) -> impl Iterator<Item = Result<ZipInput, io::Error>>
+where
+    R: BufRead,
+{
+    let mut lines = rdr.lines();
+    let mut pending = None;
+    std::iter::from_fn(move || {
+        loop {
+            match lines.next() {
+                Some(Ok(line)) if line.trim().is_empty() => {}
+                Some(Ok(line)) => {
+                    if let Some(input) = push_entry_line(&mut pending, &line) {
+                        return Some(Ok(input));
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => return pending.take().map(Ok),
+            }
+        }
+    })
+}
+
+/// Order in which the zips are processed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputOrder {
+    #[default]
+    AsGiven,
+    SmallestFirst,
+    LargestFirst,
+    /// By modification time, oldest first.
+    OldestFirst,
+}
+
+/// Reorders the inputs; inputs that cannot be stat'ed keep their relative order at the end.
+///
+/// All inputs are read (and stat'ed) before returning.
+pub fn order_zinputs<I>(zinputs: I, order: InputOrder) -> Vec<Result<ZipInput, io::Error>>
+where
+    I: Iterator<Item = Result<ZipInput, io::Error>>,
+{
+    let all: Vec<Result<ZipInput, io::Error>> = zinputs.collect();
+    if order == InputOrder::AsGiven {
+        return all;
+    }
+    let (mut ok, mut rest): (Vec<_>, Vec<_>) = (Vec::new(), Vec::new());
+    for res in all {
+        match res {
+            Ok(input) => match fs::metadata(&input.path) {
+                Ok(meta) => ok.push((meta.len(), meta.modified().ok(), input)),
+                Err(_) => rest.push(Ok(input)),
+            },
+            Err(e) => rest.push(Err(e)),
+        }
+    }
+    match order {
+        InputOrder::AsGiven => {}
+        InputOrder::SmallestFirst => ok.sort_by_key(|(len, _, _)| *len),
+        InputOrder::LargestFirst => ok.sort_by_key(|(len, _, _)| Reverse(*len)),
+        InputOrder::OldestFirst => ok.sort_by_key(|(_, modified, _)| *modified),
+    }
+    ok.into_iter()
+        .map(|(_, _, input)| Ok(input))
+        .chain(rest)
+        .collect()
+}
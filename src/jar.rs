@@ -0,0 +1,42 @@
+// JAR manifest (META-INF/MANIFEST.MF) support.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+const JAR_EXTENSIONS: &[&str] = &["jar", "war", "ear"];
+
+pub fn is_jar_name(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| JAR_EXTENSIONS.iter().any(|j| ext.eq_ignore_ascii_case(j)))
+        .unwrap_or(false)
+}
+
+/// Parses the main section of a manifest into metadata keys.
+///
+/// `Implementation-Title` becomes `JarImplementationTitle` and so on.
+pub fn main_attributes(manifest: &str) -> BTreeMap<String, String> {
+    let mut attrs: Vec<(String, String)> = Vec::new();
+    for line in manifest.lines() {
+        if line.trim().is_empty() {
+            // The main section ends at the first blank line.
+            break;
+        }
+        if let Some(continued) = line.strip_prefix(' ') {
+            if let Some((_, value)) = attrs.last_mut() {
+                value.push_str(continued);
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            attrs.push((name.trim().to_string(), value.trim_start().to_string()));
+        }
+    }
+    attrs
+        .into_iter()
+        .map(|(name, value)| (format!("Jar{}", name.replace('-', "")), value))
+        .collect()
+}
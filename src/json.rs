@@ -0,0 +1,258 @@
+// JSON serialization helpers.
+
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter, PrettyFormatter};
+use std::io::{self, Write};
+
+/// How the records are laid out in the output.
+///
+/// Partition files are always NDJSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// One compact record per line.
+    #[default]
+    Ndjson,
+    /// A single JSON array of compact records, one per line.
+    JsonArray,
+    /// Indented records, one after another.
+    Pretty,
+}
+
+/// Escapes every non-ASCII character of the strings as `\uXXXX`.
+///
+/// Structural output (separators, indentation) is delegated to the inner formatter.
+pub struct AsciiFormatter<F> {
+    inner: F,
+}
+
+impl<F> AsciiFormatter<F> {
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+impl<F> Formatter for AsciiFormatter<F>
+where
+    F: Formatter,
+{
+    fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        let mut start = 0;
+        for (i, c) in fragment.char_indices() {
+            if c.is_ascii() {
+                continue;
+            }
+            writer.write_all(&fragment.as_bytes()[start..i])?;
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                write!(writer, "\\u{:04x}", unit)?;
+            }
+            start = i + c.len_utf8();
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+
+    fn begin_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.begin_array(writer)
+    }
+
+    fn end_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.end_array(writer)
+    }
+
+    fn begin_array_value<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.end_array_value(writer)
+    }
+
+    fn begin_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.begin_object(writer)
+    }
+
+    fn end_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.end_object(writer)
+    }
+
+    fn begin_object_key<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.begin_object_key(writer, first)
+    }
+
+    fn end_object_key<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.end_object_key(writer)
+    }
+
+    fn begin_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.begin_object_value(writer)
+    }
+
+    fn end_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        self.inner.end_object_value(writer)
+    }
+}
+
+pub fn to_writer<W, T>(wtr: &mut W, value: &T, ascii_only: bool, pretty: bool) -> io::Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    match (ascii_only, pretty) {
+        (true, true) => {
+            let mut ser = serde_json::Serializer::with_formatter(
+                wtr,
+                AsciiFormatter::new(PrettyFormatter::new()),
+            );
+            value.serialize(&mut ser)?;
+        }
+        (true, false) => {
+            let mut ser =
+                serde_json::Serializer::with_formatter(wtr, AsciiFormatter::new(CompactFormatter));
+            value.serialize(&mut ser)?;
+        }
+        (false, true) => serde_json::to_writer_pretty(wtr, value)?,
+        (false, false) => serde_json::to_writer(wtr, value)?,
+    }
+    Ok(())
+}
+
+/// Turns newline-terminated compact records written to it into one JSON array.
+///
+/// `finish` writes the closing bracket; an empty output becomes `[]`.
+pub struct JsonArrayWriter<W: Write> {
+    inner: W,
+    started: bool,
+    // The newline ending the last record, held back until it is known whether a comma follows.
+    pending: bool,
+}
+
+impl<W: Write> JsonArrayWriter<W> {
+    pub fn new(inner: W) -> Self {
+        JsonArrayWriter {
+            inner,
+            started: false,
+            pending: false,
+        }
+    }
+
+    /// The writer of the array, e.g. to take out what was written so far.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.started {
+            self.inner.write_all(b"\n]\n")?;
+        } else {
+            self.inner.write_all(b"[]\n")?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for JsonArrayWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let (record, newline) = match rest.iter().position(|&b| b == b'\n') {
+                Some(ix) => (&rest[..ix], true),
+                None => (rest, false),
+            };
+            if !record.is_empty() {
+                if !self.started {
+                    self.inner.write_all(b"[\n")?;
+                    self.started = true;
+                } else if self.pending {
+                    self.inner.write_all(b",\n")?;
+                }
+                self.pending = false;
+                self.inner.write_all(record)?;
+            }
+            if newline {
+                self.pending = true;
+                rest = &rest[record.len() + 1..];
+            } else {
+                rest = &[];
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn ascii(value: &Value, pretty: bool) -> Result<String, Box<dyn std::error::Error>> {
+        let mut out = Vec::new();
+        to_writer(&mut out, value, true, pretty)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn non_ascii_characters_are_escaped() -> Result<(), Box<dyn std::error::Error>> {
+        let value = json!({"名前": "é\n😀x"});
+        // The astral character as a surrogate pair.
+        assert_eq!(
+            ascii(&value, false)?,
+            r#"{"\u540d\u524d":"\u00e9\n\ud83d\ude00x"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn escaped_output_reads_back_the_same() -> Result<(), Box<dyn std::error::Error>> {
+        let value = json!({
+            "ascii": "plain \"quoted\" \\ text\t",
+            "latin": "Ça déjà",
+            "cjk": ["日本語", "한국어"],
+            "astral": "𝄞🎉\u{10ffff}",
+            "ß": 1,
+        });
+        for pretty in [false, true] {
+            let out = ascii(&value, pretty)?;
+            assert!(out.is_ascii(), "{}", out);
+            assert_eq!(serde_json::from_str::<Value>(&out)?, value);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+// Record layouts: metadata nested under `metadata` or flattened to the top level.
+
+use crate::Blob;
+use crate::rewrite::{Entries, Rewrite, serialize_rewritten};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// Prefix of the flattened metadata keys, e.g. `metadata_ZipName`.
+pub const FLAT_METADATA_PREFIX: &str = "metadata_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// Metadata fields under `metadata` (default).
+    #[default]
+    Nested,
+    /// Metadata fields at the top level, prefixed with `metadata_`.
+    Flat,
+}
+
+/// Serializes a blob with its metadata flattened to prefixed top-level fields.
+pub struct FlatBlob<'a>(pub &'a Blob<'a>);
+
+// Splices the entries of `metadata` into the record, their keys prefixed.
+struct FlattenMetadata;
+
+impl Rewrite for FlattenMetadata {
+    fn entry<M, V>(&self, map: &mut M, key: &str, value: &V) -> Result<(), M::Error>
+    where
+        M: SerializeMap,
+        V: Serialize + ?Sized,
+    {
+        match key {
+            "metadata" => value.serialize(Entries::new(map, &PrefixKeys)),
+            key => map.serialize_entry(key, value),
+        }
+    }
+}
+
+// Prefixes the keys of the metadata entries with `FLAT_METADATA_PREFIX`.
+struct PrefixKeys;
+
+impl Rewrite for PrefixKeys {
+    fn entry<M, V>(&self, map: &mut M, key: &str, value: &V) -> Result<(), M::Error>
+    where
+        M: SerializeMap,
+        V: Serialize + ?Sized,
+    {
+        map.serialize_entry(&PrefixedKey(key), value)
+    }
+}
+
+// A metadata key with `FLAT_METADATA_PREFIX`, written without allocating.
+struct PrefixedKey<'k>(&'k str);
+
+impl Serialize for PrefixedKey<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&format_args!("{}{}", FLAT_METADATA_PREFIX, self.0))
+    }
+}
+
+impl Serialize for FlatBlob<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_rewritten(self.0, serializer, &FlattenMetadata)
+    }
+}
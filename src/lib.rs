@@ -1,11 +1,103 @@
 use base64::{Engine as _, engine::general_purpose};
-use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
-use rawzip::{ZipArchive, time::ZipDateTimeKind};
-use serde::Serialize;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use rawzip::time::ZipDateTimeKind;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
-use std::fs::File;
-use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::time::{Instant, UNIX_EPOCH};
+
+mod archive;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod check;
+mod checkpoint;
+mod compress;
+mod content_encoding;
+mod crypto;
+mod decompress;
+mod diagnostic;
+mod encoding;
+mod entry;
+mod error;
+mod external;
+mod fieldmap;
+mod filename;
+mod format;
+mod glob;
+#[cfg(feature = "http-sink")]
+mod http_sink;
+mod input;
+mod jar;
+mod json;
+mod layout;
+mod limit;
+mod mime;
+mod ooxml;
+mod parallel;
+mod partition;
+mod pattern;
+mod prefetch;
+mod progress;
+mod rewrite;
+mod rotate;
+mod sink;
+mod stats;
+mod tarball;
+mod text;
+mod transform;
+mod tree;
+mod validate;
+mod walk;
+
+pub use archive::{ArchiveEntry, ArchiveReader};
+#[cfg(feature = "tokio")]
+pub use async_io::{
+    buf2zip2blobs2jsons2writer_async, file2buf_async, rdr2buf_async,
+    rdr2zfilenames2zip2blobs2jsons2writer_async, rdr2zip2blobs2jsons2writer_async,
+};
+pub use check::{
+    Verdict, ZipCheck, buf2check, dir2checks2writer, stdin2zfilenames2checks2writer, zinput2check,
+    zinputs2checks2writer,
+};
+pub use checkpoint::{Checkpoint, FileStamp};
+pub use compress::{CompressedWriter, OutputCompression};
+pub use content_encoding::{EncodingRule, EncodingRuleError};
+pub use decompress::{UnsupportedMethod, method_name};
+pub use diagnostic::{Diagnostic, DiagnosticSink, Level, LogFormat, LogSink};
+pub use encoding::BodyEncoding;
+pub use entry::{ArchiveEntries, CentralRecord, EntryHandle, EntryMetadata, Handles};
+pub use error::Error;
+pub use fieldmap::{FieldMap, FieldMapError, FieldRename, MappedRecord};
+pub use filename::FilenameEncoding;
+pub use format::{ArchiveFormat, detect_format};
+pub use glob::{Glob, GlobError};
+#[cfg(feature = "http-sink")]
+pub use http_sink::{BLOB_CONTENT_ENCODING_HEADER, BLOB_CONTENT_TYPE_HEADER, HttpSink};
+pub use input::{InputFormat, InputOrder, ZipInput, order_zinputs, rdr2zentries, rdr2zinputs};
+pub use json::{JsonArrayWriter, OutputFormat};
+pub use layout::{FlatBlob, Layout};
+pub use limit::OutputLimit;
+pub use mime::ContentTypeDetection;
+pub use partition::{
+    DEFAULT_MAX_OPEN_PARTITIONS, DEFAULT_PARTITION, LAST_MODIFIED_DATE_FIELD, PartitionedOutput,
+};
+pub use pattern::{PathPattern, PatternError};
+pub use progress::{Progress, ProgressLog, ProgressSink};
+pub use rotate::RotatingWriter;
+pub use sink::BlobSink;
+pub use stats::{RunStats, StatsRecorder};
+pub use tarball::{TarEntries, TarEntry};
+pub use text::TextStats;
+pub use transform::BlobTransform;
+pub use tree::{ArchiveTree, TreeNode};
+pub use validate::{
+    VerifyReport, Violation, jsons2violations2writer, stdin2jsons2violations2stdout, validate_blob,
+};
+pub use walk::{DirZipNames, dir2zfilenames};
 
 // A custom error type to distinguish I/O errors from size limit errors.
 #[derive(Debug)]
@@ -29,22 +121,211 @@ impl From<io::Error> for ReadError {
     }
 }
 
-#[derive(Serialize, Debug)]
-pub struct Metadata {
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Metadata<'a> {
     #[serde(rename = "ZipName")]
-    pub zip_name: String,
+    pub zip_name: Cow<'a, str>,
+    /// Archive-level fields shared by every record of the archive.
+    #[serde(flatten)]
+    pub extra: Cow<'a, BTreeMap<String, String>>,
 }
 
-#[derive(Serialize, Debug)]
-pub struct Blob {
+/// The record of one entry (or archive).
+///
+/// The fields shared by the blobs of an archive (e.g. its name) are borrowed, and so is
+/// the body, encoded into a buffer reused from entry to entry: see [`Blob::into_owned`]
+/// to keep a blob past the conversion.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Blob<'a> {
     pub name: String,
-    pub content_type: String,
-    pub content_encoding: String,
-    pub content_transfer_encoding: String,
-    pub body: String,
-    pub metadata: Metadata,
+    /// Base64 of the bytes of the entry name, when they could not be decoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_raw: Option<String>,
+    pub content_type: Cow<'a, str>,
+    pub content_encoding: Cow<'a, str>,
+    pub content_transfer_encoding: Cow<'a, str>,
+    /// Body in `content_transfer_encoding`; omitted when the body was routed to the external store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Cow<'a, str>>,
+    pub metadata: Metadata<'a>,
     pub content_length: u64,
     pub last_modified: String,
+    /// Location of the body in the external store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_uri: Option<String>,
+    /// Hex CRC-32 of the entry, as recorded in the zip headers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<String>,
+    /// Hex SHA-256 of the (decoded) body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extracted_text: Option<String>,
+    #[serde(flatten)]
+    pub text_stats: Option<TextStats>,
+    #[serde(flatten)]
+    pub extended: Option<ExtendedMetadata>,
+    #[serde(flatten)]
+    pub range: Option<DataRange>,
+    /// Set when the body holds only the first `Options::max_item_size` bytes of the entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<bool>,
+    /// Which part of a chunked entry the body is.
+    #[serde(flatten)]
+    pub part: Option<Part>,
+}
+
+impl Blob<'_> {
+    /// The blob with its borrowed fields copied.
+    pub fn into_owned(self) -> Blob<'static> {
+        Blob {
+            content_type: Cow::Owned(self.content_type.into_owned()),
+            content_encoding: Cow::Owned(self.content_encoding.into_owned()),
+            content_transfer_encoding: Cow::Owned(self.content_transfer_encoding.into_owned()),
+            body: self.body.map(|body| Cow::Owned(body.into_owned())),
+            metadata: Metadata {
+                zip_name: Cow::Owned(self.metadata.zip_name.into_owned()),
+                extra: Cow::Owned(self.metadata.extra.into_owned()),
+            },
+            name: self.name,
+            name_raw: self.name_raw,
+            content_length: self.content_length,
+            last_modified: self.last_modified,
+            body_uri: self.body_uri,
+            crc32: self.crc32,
+            sha256: self.sha256,
+            extracted_text: self.extracted_text,
+            text_stats: self.text_stats,
+            extended: self.extended,
+            range: self.range,
+            truncated: self.truncated,
+            part: self.part,
+        }
+    }
+}
+
+/// The place of a body among the parts of an entry split by `OversizePolicy::Chunk`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Part {
+    /// From 1.
+    pub part: u64,
+    pub total_parts: u64,
+}
+
+/// Where the data of an entry is stored in the archive, added with `Options::offsets`:
+/// the `compressed_size` bytes from `offset` are the entry as compressed with `compression_method`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DataRange {
+    pub offset: u64,
+    /// Left to [`ExtendedMetadata`] when the blob has it, as is `compression_method`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_method: Option<String>,
+}
+
+/// Header fields of an entry, added with `Options::extended_metadata`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedMetadata {
+    /// Name of the compression method, e.g. `deflate`.
+    pub compression_method: String,
+    pub compressed_size: u64,
+    /// Octal Unix file type and permissions, e.g. `100644`.
+    pub unix_mode: String,
+    pub external_attributes: u32,
+    pub is_dir: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub entry_comment: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub archive_comment: String,
+}
+
+// Content types of the archive records, by format.
+fn archive_content_type(format: ArchiveFormat) -> &'static str {
+    match format {
+        ArchiveFormat::Tar => "application/x-tar",
+        ArchiveFormat::Gzip => "application/gzip",
+        ArchiveFormat::Zstd => "application/zstd",
+        _ => "application/zip",
+    }
+}
+
+/// Written to the output in place of blobs for inputs that must be reported, not skipped.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ErrorRecord {
+    pub error: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    pub message: String,
+}
+
+/// A blob [`buf2zip2blobs`] could not produce.
+#[derive(Debug)]
+pub enum BlobError {
+    /// An entry that was not converted; the record written in place of its blob.
+    Entry(ErrorRecord),
+    /// The zip could not be read; no blob follows.
+    Archive(Error),
+}
+
+impl fmt::Display for BlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobError::Entry(record) => write!(f, "{}: {}", record.error, record.message),
+            BlobError::Archive(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for BlobError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlobError::Entry(_) => None,
+            BlobError::Archive(e) => Some(e),
+        }
+    }
+}
+
+impl From<Error> for BlobError {
+    fn from(err: Error) -> BlobError {
+        BlobError::Archive(err)
+    }
+}
+
+fn error_record2writer<W>(
+    record: &ErrorRecord,
+    options: &Options,
+    wtr: &mut W,
+) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    record2writer(record, options, wtr)
+}
+
+// Writes one record followed by a newline, in the layout of `options.output_format`.
+fn record2writer<T, W>(record: &T, options: &Options, wtr: &mut W) -> Result<(), io::Error>
+where
+    T: Serialize + ?Sized,
+    W: Write,
+{
+    let pretty = options.output_format == OutputFormat::Pretty;
+    let mut counted = stats::CountingWriter::new(wtr);
+    json::to_writer(&mut counted, record, options.ascii_only, pretty)?;
+    writeln!(counted)?;
+    let written = counted.count();
+    options.count(|stats| stats.bytes_out += written);
+    if let Some(limit) = options.output_limit {
+        limit.add(written);
+    }
+    Ok(())
+}
+
+// What the conversion of one entry produces.
+enum EntryRecord<'a> {
+    Blob(Box<Blob<'a>>),
+    Error(ErrorRecord),
 }
 
 fn zip_datetime_to_chrono_utc(zdt: &ZipDateTimeKind) -> DateTime<Utc> {
@@ -68,7 +349,7 @@ pub fn rdr2buf<R>(rdr: R, buf: &mut Vec<u8>, limit: u64) -> Result<(), ReadError
 where
     R: Read,
 {
-    let mut taken = rdr.take(limit + 1);
+    let mut taken = rdr.take(limit.saturating_add(1));
     buf.clear();
     taken.read_to_end(buf)?;
     if buf.len() as u64 > limit {
@@ -96,67 +377,1213 @@ fn stdin2filenames() -> impl Iterator<Item = Result<String, io::Error>> {
     rdr2filenames(io::stdin().lock())
 }
 
+fn find_handle<'a>(
+    entries: &'a dyn ArchiveReader,
+    path: &str,
+) -> Option<Box<dyn ArchiveEntry + 'a>> {
+    entries
+        .entries()
+        .filter_map(Result::ok)
+        .find(|handle| handle.name() == path)
+}
+
+// Decoded data of the entry at `path`, if any.
+fn find_entry(entries: &dyn ArchiveReader, path: &str, options: &Options) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    find_handle(entries, path)?
+        .read_body_with_password(&mut data, options.password)
+        .ok()?;
+    Some(data)
+}
+
+// Metadata captured from the path of the zip by `Options::path_pattern`.
+fn path_metadata(zip_name: &str, options: &Options) -> BTreeMap<String, String> {
+    options
+        .path_pattern
+        .and_then(|pattern| pattern.captures(zip_name))
+        .unwrap_or_default()
+}
+
+// Metadata shared by the entries of `entries`, the zip named `archive_name` (`zip_name`
+// itself, or a zip nested in it).
+fn archive_metadata(
+    entries: &dyn ArchiveReader,
+    zip_name: &str,
+    archive_name: &str,
+    options: &Options,
+    is_ooxml: bool,
+) -> BTreeMap<String, String> {
+    let mut extra = path_metadata(zip_name, options);
+    if options.jar_manifest && jar::is_jar_name(archive_name) {
+        let attrs = find_entry(entries, jar::MANIFEST_PATH, options)
+            .map(|manifest| jar::main_attributes(&String::from_utf8_lossy(&manifest)))
+            .unwrap_or_default();
+        extra.extend(attrs);
+    }
+    if is_ooxml {
+        let core = find_entry(entries, ooxml::CORE_PROPERTIES_PATH, options)
+            .and_then(|data| String::from_utf8(data).ok())
+            .map(|xml| ooxml::core_properties(&xml))
+            .unwrap_or_default();
+        extra.extend(core);
+    }
+    extra
+}
+
+/// Writes one blob as a JSON line using the configured layout.
+///
+/// The line goes to `Options::sink` (without the newline), or else to the partition file
+/// of the blob when `Options::partitions` is set, instead of `wtr`.
+pub fn blob2writer<W>(blob: &Blob, options: &Options, wtr: &mut W) -> Result<(), Error>
+where
+    W: Write,
+{
+    match (options.sink, options.partitions) {
+        (Some(sink), _) => {
+            let ndjson = Options {
+                output_format: OutputFormat::Ndjson,
+                ..*options
+            };
+            let mut record = Vec::new();
+            blob2jsonl(blob, &ndjson, &mut record)?;
+            let record = record.strip_suffix(b"\n").unwrap_or(&record);
+            sink.send(blob, record)
+        }
+        (None, Some(partitions)) => partitions.write_with(blob, |file| {
+            let ndjson = Options {
+                output_format: OutputFormat::Ndjson,
+                ..*options
+            };
+            blob2jsonl(blob, &ndjson, file)
+        }),
+        (None, None) => blob2jsonl(blob, options, wtr),
+    }
+    .map_err(Error::from)
+}
+
+fn blob2jsonl<W>(blob: &Blob, options: &Options, wtr: &mut W) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    match (options.layout, options.field_map) {
+        (Layout::Nested, None) => record2writer(blob, options, wtr),
+        (Layout::Flat, None) => record2writer(&FlatBlob(blob), options, wtr),
+        (Layout::Nested, Some(map)) => record2writer(&MappedRecord(map, blob), options, wtr),
+        (Layout::Flat, Some(map)) => {
+            record2writer(&MappedRecord(map, &FlatBlob(blob)), options, wtr)
+        }
+    }
+}
+
+/// Converts the entries of `zipdata`, named `zip_name` in the metadata, into blobs
+/// instead of JSON lines.
+///
+/// The blobs of the zip are all built before the first is returned. Only the entry
+/// options apply (no archive record, layout or output format).
+pub fn buf2zip2blobs(
+    zip_name: &str,
+    zipdata: &[u8],
+    options: &Options,
+) -> impl Iterator<Item = Result<Blob<'static>, BlobError>> + use<> {
+    let mut blobs = Vec::new();
+    let converted = zip_entries(zipdata, options).and_then(|entries| {
+        entries2blobs(zip_name, &entries, options, |record| {
+            blobs.push(match record {
+                EntryRecord::Blob(blob) => Ok(blob.into_owned()),
+                EntryRecord::Error(record) => Err(BlobError::Entry(record)),
+            });
+            Ok(())
+        })
+    });
+    if let Err(e) = converted {
+        blobs.push(Err(BlobError::Archive(Error::from(e))));
+    }
+    blobs.into_iter()
+}
+
+// The entries of the zip `zipdata`, their names decoded in `options.filename_encoding`.
+fn zip_entries<'a>(zipdata: &'a [u8], options: &Options) -> Result<ArchiveEntries<'a>, io::Error> {
+    ArchiveEntries::from_slice(zipdata)
+        .map(|entries| entries.with_filename_encoding(options.filename_encoding))
+}
+
 pub fn buf2zip2blobs2jsons2writer<W>(
     zip_name: &str,
     zipdata: &[u8],
-    content_type: &str,
-    content_encoding: &str,
-    max_item_size: u64,
-    verbose: bool,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    let entries = zip_entries(zipdata, options)?;
+    entries2zip2blobs2jsons2writer(zip_name, &entries, options, wtr).map_err(Error::from)
+}
+
+/// Same as [`buf2zip2blobs2jsons2writer`] for the tar `tardata` (not compressed).
+pub fn buf2tar2blobs2jsons2writer<W>(
+    tar_name: &str,
+    tardata: &[u8],
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    let entries = TarEntries::from_slice(tardata)?;
+    entries2zip2blobs2jsons2writer(tar_name, &entries, options, wtr).map_err(Error::from)
+}
+
+fn entries2zip2blobs2jsons2writer<W>(
+    zip_name: &str,
+    entries: &dyn ArchiveReader,
+    options: &Options,
     wtr: &mut BufWriter<W>,
 ) -> Result<(), io::Error>
 where
     W: Write,
 {
-    let archive = ZipArchive::from_slice(zipdata).map_err(io::Error::other)?;
-
-    for entry_result in archive.entries() {
-        let entry_header = entry_result.map_err(io::Error::other)?;
-        let wayfinder = entry_header.wayfinder();
-        let entry = archive.get_entry(wayfinder).map_err(io::Error::other)?;
-        let entry_data = entry.data();
-        let file_name = String::from_utf8_lossy(entry_header.file_path().as_bytes()).to_string();
-
-        if entry_data.len() as u64 > max_item_size {
-            if verbose {
-                eprintln!(
-                    "level:warn\tstatus:item_skipped\treason:size_limit_exceeded\tpath:{}\titem:{}\tsize:{}",
-                    zip_name,
-                    file_name,
-                    entry_data.len()
-                );
+    match options.output_mode {
+        OutputMode::Flat => entries2blobs(zip_name, entries, options, |record| match record {
+            EntryRecord::Blob(blob) => Ok(blob2writer(&blob, options, wtr)?),
+            EntryRecord::Error(record) => error_record2writer(&record, options, wtr),
+        }),
+        OutputMode::Tree => {
+            let mut builder = tree::TreeBuilder::default();
+            // Error records precede the document of the zip.
+            entries2blobs(zip_name, entries, options, |record| match record {
+                EntryRecord::Blob(mut blob) => {
+                    if !options.tree_bodies {
+                        blob.body = None;
+                    }
+                    builder.insert(blob.into_owned());
+                    Ok(())
+                }
+                EntryRecord::Error(record) => error_record2writer(&record, options, wtr),
+            })?;
+            let tree = builder.build(zip_name);
+            record2writer(&tree, options, wtr)
+        }
+    }
+}
+
+struct BodyFields<'b> {
+    body: Option<Cow<'b, str>>,
+    transfer_encoding: BodyEncoding,
+    body_uri: Option<String>,
+    sha256: Option<String>,
+}
+
+// Encodes the body inline, into `body_buf`, or routes it to the external store when it is large.
+fn body_fields<'b>(
+    data: &[u8],
+    options: &Options,
+    body_buf: &'b mut String,
+) -> Result<BodyFields<'b>, io::Error> {
+    if options.body_mode == BodyMode::MetadataOnly {
+        return Ok(BodyFields {
+            body: None,
+            transfer_encoding: options.body_encoding,
+            body_uri: None,
+            sha256: None,
+        });
+    }
+    match options.external_dir {
+        Some(dir) if data.len() as u64 > options.external_threshold => {
+            let digest = external::sha256_hex(data);
+            let uri = external::store(dir, &digest, data)?;
+            Ok(BodyFields {
+                body: None,
+                transfer_encoding: options.body_encoding,
+                body_uri: Some(uri),
+                sha256: Some(digest),
+            })
+        }
+        _ => {
+            let transfer_encoding = options.body_encoding.encode_into(data, body_buf);
+            Ok(BodyFields {
+                body: Some(Cow::Borrowed(body_buf)),
+                transfer_encoding,
+                body_uri: None,
+                sha256: None,
+            })
+        }
+    }
+}
+
+/// Creates the blob of an input zip (or tar) itself (`ArchiveRecordMode::Also`/`Only`).
+pub fn archive2blob<'a>(
+    zip_name: &'a str,
+    zipdata: &[u8],
+    options: &Options,
+) -> Result<Blob<'a>, Error> {
+    let mut body = String::new();
+    let BodyFields {
+        body: inline_body,
+        transfer_encoding,
+        body_uri,
+        sha256,
+    } = body_fields(zipdata, options, &mut body)?;
+    let inline = inline_body.is_some();
+    let modified = fs::metadata(zip_name)
+        .and_then(|meta| meta.modified())
+        .unwrap_or(UNIX_EPOCH);
+    Ok(Blob {
+        name: zip_name.to_string(),
+        name_raw: None,
+        content_type: Cow::Borrowed(archive_content_type(detect_format(zipdata))),
+        content_encoding: Cow::Borrowed("identity"),
+        content_transfer_encoding: Cow::Borrowed(transfer_encoding.name()),
+        body: inline.then_some(Cow::Owned(body)),
+        metadata: Metadata {
+            zip_name: Cow::Borrowed(zip_name),
+            extra: Cow::Owned(path_metadata(zip_name, options)),
+        },
+        content_length: zipdata.len() as u64,
+        last_modified: DateTime::<Utc>::from(modified).to_rfc3339(),
+        body_uri,
+        crc32: None,
+        sha256: sha256.or_else(|| Some(external::sha256_hex(zipdata))),
+        extracted_text: None,
+        text_stats: None,
+        extended: None,
+        range: None,
+        truncated: None,
+        part: None,
+    })
+}
+
+// Converts each entry of the zip into a blob (or an error record) and hands it to `on_record`.
+fn entries2blobs<F>(
+    zip_name: &str,
+    entries: &dyn ArchiveReader,
+    options: &Options,
+    mut on_record: F,
+) -> Result<(), io::Error>
+where
+    F: FnMut(EntryRecord) -> Result<(), io::Error>,
+{
+    let mut listed = options.entry_names.map(Listed::new);
+    let mut blobs: u64 = 0;
+    // Set when a limit stopped the conversion, to be told apart from a failed one.
+    let mut limit_reason: Option<&str> = None;
+    let converted = nested_entries2blobs(
+        zip_name,
+        "",
+        0,
+        entries,
+        options,
+        &mut listed,
+        &mut |record| {
+            if let EntryRecord::Blob(_) = record {
+                limit_reason = options.limit_reached(blobs);
+                if limit_reason.is_some() {
+                    return Err(io::Error::other("limit reached"));
+                }
+                blobs += 1;
             }
-            continue;
+            on_record(record)
+        },
+    );
+    if let Some(reason) = limit_reason {
+        options.count(|stats| stats.zips_truncated += 1);
+        // Converted again by the next run, whose limits may differ.
+        if let Some(checkpoint) = options.checkpoint {
+            checkpoint.abandon(zip_name);
+        }
+        return options.report(Diagnostic {
+            reason: Some(reason.into()),
+            size: Some(blobs),
+            ..Diagnostic::warn("zip_truncated", Some(zip_name))
+        });
+    }
+    converted?;
+    let Some(listed) = listed else {
+        return Ok(());
+    };
+    for name in listed.missing() {
+        options.report(Diagnostic {
+            item: Some(name.into()),
+            ..Diagnostic::warn("item_not_found", Some(zip_name))
+        })?;
+        on_record(EntryRecord::Error(ErrorRecord {
+            error: "item_not_found".into(),
+            path: zip_name.into(),
+            item: Some(name.into()),
+            message: "no entry with this name".into(),
+        }))?;
+    }
+    Ok(())
+}
+
+// The entries named by `Options::entry_names`, with those found so far.
+struct Listed<'l> {
+    names: BTreeSet<&'l str>,
+    found: BTreeSet<&'l str>,
+}
+
+impl<'l> Listed<'l> {
+    fn new(names: &'l [String]) -> Self {
+        Listed {
+            names: names.iter().map(String::as_str).collect(),
+            found: BTreeSet::new(),
+        }
+    }
+
+    // Whether `name` is listed, remembering it as found.
+    fn take(&mut self, name: &str) -> bool {
+        match self.names.get(name) {
+            Some(&listed) => {
+                self.found.insert(listed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Whether an entry of the nested zip `name` is listed.
+    fn has_inside(&self, name: &str) -> bool {
+        let prefix = format!("{}!", name);
+        self.names
+            .range(prefix.as_str()..)
+            .next()
+            .is_some_and(|listed| listed.starts_with(&prefix))
+    }
+
+    fn missing(&self) -> impl Iterator<Item = &'l str> + '_ {
+        self.names.difference(&self.found).copied()
+    }
+}
+
+// Reads a DOS time, which has no time zone, in `options.dos_timezone`.
+fn entry_last_modified(
+    last_modified: DateTime<Utc>,
+    dos_time: bool,
+    options: &Options,
+) -> DateTime<Utc> {
+    if !dos_time {
+        return last_modified;
+    }
+    options
+        .dos_timezone
+        .from_local_datetime(&last_modified.naive_utc())
+        .single()
+        .map(|local| local.with_timezone(&Utc))
+        .unwrap_or(last_modified)
+}
+
+// Bytes counted per buffered entry header besides its name, as in a central directory.
+const ENTRY_HEADER_LEN: u64 = 46;
+
+type EntryIter<'a> = Box<dyn Iterator<Item = Result<Box<dyn ArchiveEntry + 'a>, io::Error>> + 'a>;
+
+// The entries of `entries` in `options.entry_order`, ties kept in name order.
+fn ordered_entries<'a>(
+    entries: &'a dyn ArchiveReader,
+    options: &Options,
+) -> Result<EntryIter<'a>, io::Error> {
+    if options.entry_order == EntryOrder::Archive {
+        return Ok(entries.entries());
+    }
+    let mut buffered = Vec::new();
+    let mut buffered_len: u64 = 0;
+    for handle in entries.entries() {
+        let handle = handle?;
+        let meta = handle.metadata();
+        buffered_len += ENTRY_HEADER_LEN + meta.name.len() as u64;
+        if buffered_len > options.max_zip_size {
+            return Err(io::Error::other(
+                "entry headers to sort exceed the zip size limit",
+            ));
         }
+        buffered.push((meta, handle));
+    }
+    buffered.sort_by(|(a, _), (b, _)| {
+        let by_key = match options.entry_order {
+            EntryOrder::Archive | EntryOrder::Name => std::cmp::Ordering::Equal,
+            EntryOrder::Mtime => a.last_modified.cmp(&b.last_modified),
+            EntryOrder::Size => a.uncompressed_size.cmp(&b.uncompressed_size),
+        };
+        by_key.then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(Box::new(buffered.into_iter().map(|(_, handle)| Ok(handle))))
+}
+
+/// Metadata key of the path of an entry of a nested zip, e.g. `outer.zip!inner.zip!a.txt`.
+pub const NESTED_PATH_FIELD: &str = "NestedPath";
+
+// Jars (and wars and ears) are zips too, with a manifest.
+fn is_zip_name(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".zip") || jar::is_jar_name(name)
+}
+
+// What the blobs of an entry are built from, beyond the data.
+struct BlobContext<'c> {
+    options: &'c Options<'c>,
+    zip_name: &'c str,
+    name: &'c str,
+    file_name: &'c str,
+    decoded_name: &'c str,
+    meta: &'c EntryMetadata,
+    entry: &'c dyn ArchiveEntry,
+    extra: &'c BTreeMap<String, String>,
+    archive_comment: &'c str,
+    is_ooxml: bool,
+    depth: usize,
+    encoding_rule: Option<&'c EncodingRule>,
+}
+
+impl<'c> BlobContext<'c> {
+    fn content_type(&self, data: &[u8]) -> &'c str {
+        mime::detect(self.options.detect_content_type, self.decoded_name, data)
+            .unwrap_or(self.options.content_type)
+    }
 
-        let dt: DateTime<Utc> = zip_datetime_to_chrono_utc(&entry_header.last_modified());
+    // The blob of `data` (the entry, or a part of it), its body encoded into `body_buf`.
+    fn blob<'b>(
+        &self,
+        data: &[u8],
+        crc32: Option<u32>,
+        body_buf: &'b mut String,
+    ) -> Result<Blob<'b>, io::Error>
+    where
+        'c: 'b,
+    {
+        let options = self.options;
+        let meta = self.meta;
+        let extracted_text =
+            if self.is_ooxml && options.ooxml_text && ooxml::is_text_part(self.file_name) {
+                std::str::from_utf8(data)
+                    .ok()
+                    .and_then(|xml| ooxml::part_text(self.file_name, xml))
+            } else {
+                None
+            };
+
+        let text_stats = if options.text_stats && text::is_text(data) {
+            Some(text::text_stats(data))
+        } else {
+            None
+        };
+
+        let BodyFields {
+            body,
+            transfer_encoding,
+            body_uri,
+            sha256,
+        } = body_fields(data, options, body_buf)?;
+
+        let extended = if options.extended_metadata {
+            let record = self.entry.central_record()?;
+            Some(ExtendedMetadata {
+                compression_method: method_name(meta.compression_method).to_string(),
+                compressed_size: meta.compressed_size,
+                unix_mode: format!("{:o}", meta.mode),
+                external_attributes: record.external_attributes,
+                is_dir: meta.is_dir,
+                entry_comment: record.comment,
+                archive_comment: self.archive_comment.to_string(),
+            })
+        } else {
+            None
+        };
+
+        // Nested entries are not stored in the input itself.
+        let range = if options.offsets && self.depth == 0 {
+            let stored = extended.is_none();
+            Some(DataRange {
+                offset: self.entry.data_offset()?,
+                compressed_size: stored.then_some(meta.compressed_size),
+                compression_method: stored
+                    .then(|| method_name(meta.compression_method).to_string()),
+            })
+        } else {
+            None
+        };
+
+        let extra = if self.depth > 0 {
+            let mut entry_extra = self.extra.clone();
+            entry_extra.insert(
+                NESTED_PATH_FIELD.to_string(),
+                format!("{}!{}", self.zip_name, self.name),
+            );
+            Cow::Owned(entry_extra)
+        } else {
+            Cow::Borrowed(self.extra)
+        };
 
-        let blob = Blob {
-            name: file_name,
-            content_type: content_type.to_string(),
-            content_encoding: content_encoding.to_string(),
-            content_transfer_encoding: "base64".to_string(),
-            body: general_purpose::STANDARD.encode(entry_data),
+        Ok(Blob {
+            name: self.name.to_string(),
+            name_raw: meta
+                .raw_name
+                .as_ref()
+                .map(|raw| general_purpose::STANDARD.encode(raw)),
+            content_type: Cow::Borrowed(self.content_type(data)),
+            content_encoding: Cow::Borrowed(
+                self.encoding_rule
+                    .map_or(options.content_encoding, |rule| rule.encoding.as_str()),
+            ),
+            content_transfer_encoding: Cow::Borrowed(transfer_encoding.name()),
+            body,
             metadata: Metadata {
-                zip_name: zip_name.to_string(),
+                zip_name: Cow::Borrowed(self.zip_name),
+                extra,
+            },
+            content_length: data.len() as u64,
+            last_modified: entry_last_modified(meta.last_modified, meta.dos_time, options)
+                .to_rfc3339(),
+            body_uri,
+            crc32: crc32.map(|crc| format!("{:08x}", crc)),
+            sha256: match options.checksum {
+                Checksum::Crc32 => sha256,
+                Checksum::Sha256 => sha256.or_else(|| Some(external::sha256_hex(data))),
             },
-            content_length: entry_data.len() as u64,
-            last_modified: dt.to_rfc3339(),
+            extracted_text,
+            text_stats,
+            extended,
+            range,
+            truncated: None,
+            part: None,
+        })
+    }
+}
+
+// Converts the entries of a zip nested `depth` levels deep in the zip `zip_name`;
+// `prefix` holds the names of the zips containing it, each followed by `!`.
+fn nested_entries2blobs(
+    zip_name: &str,
+    prefix: &str,
+    depth: usize,
+    entries: &dyn ArchiveReader,
+    options: &Options,
+    listed: &mut Option<Listed>,
+    on_record: &mut dyn FnMut(EntryRecord) -> Result<(), io::Error>,
+) -> Result<(), io::Error> {
+    let archive_name = match prefix.strip_suffix('!') {
+        Some(nested) => nested.rsplit('!').next().unwrap_or(nested),
+        None => zip_name,
+    };
+    let is_ooxml = options.ooxml && find_handle(entries, ooxml::CONTENT_TYPES_PATH).is_some();
+    let extra = archive_metadata(entries, zip_name, archive_name, options, is_ooxml);
+    let archive_comment = if options.extended_metadata {
+        entries.comment()?
+    } else {
+        String::new()
+    };
+    let mut entry_data: Vec<u8> = Vec::new();
+    // The encoded body of the blob being emitted, reused from entry to entry.
+    let mut body_buf = String::new();
+
+    for handle_result in ordered_entries(entries, options)? {
+        let handle = handle_result?;
+        let meta = handle.metadata();
+        let file_name = meta.name.as_str();
+        let name = format!("{}{}", prefix, file_name);
+
+        let oversized = meta.uncompressed_size > options.max_item_size;
+        // Nested zips are opened whatever the globs; their entries are selected instead.
+        let mut nested = !oversized && depth < options.max_nesting_depth && is_zip_name(file_name);
+        if let Some(listed) = listed.as_mut() {
+            if listed.take(&name) {
+                // Listed itself, not for its entries.
+                nested = false;
+            } else if !(nested && listed.has_inside(&name)) {
+                continue;
+            }
+        }
+        if !nested && !glob::is_selected(file_name, options.include, options.exclude) {
+            continue;
+        }
+
+        let encoding_rule = content_encoding::matching(options.encoding_rules, file_name);
+        // The name of the entry once decoded (e.g. `a.json` for `a.json.gz`), for its content type.
+        let decoded_name = encoding_rule
+            .and_then(|rule| rule.strip(file_name))
+            .unwrap_or(file_name);
+        let name = if options.strip_encoding_suffix {
+            format!("{}{}", prefix, decoded_name)
+        } else {
+            name
+        };
+
+        if meta.raw_name.is_some() {
+            options.report(Diagnostic {
+                item: Some(name.clone()),
+                ..Diagnostic::warn("name_undecodable", Some(zip_name))
+            })?;
+        }
+
+        if oversized && options.oversize_policy == OversizePolicy::Skip {
+            options.report(Diagnostic {
+                reason: Some("size_limit_exceeded".into()),
+                item: Some(name),
+                size: Some(meta.uncompressed_size),
+                ..Diagnostic::warn("item_skipped", Some(zip_name))
+            })?;
+            continue;
+        }
+
+        let context = BlobContext {
+            options,
+            zip_name,
+            name: &name,
+            file_name,
+            decoded_name,
+            meta: &meta,
+            entry: handle.as_ref(),
+            extra: &extra,
+            archive_comment: &archive_comment,
+            is_ooxml,
+            depth,
+            encoding_rule,
+        };
+        // The CRC-32 of the whole entry, for its truncated body or parts.
+        let header_crc32 = handle.has_crc32().then_some(meta.crc32);
+
+        // The data of the top-level entries is left unread when the headers say all.
+        if depth == 0 && !oversized && !nested && options.headers_suffice() {
+            let blob = Blob {
+                content_length: meta.uncompressed_size,
+                ..context.blob(&[], header_crc32, &mut body_buf)?
+            };
+            if let Some(blob) = options.transformed(blob) {
+                on_record(EntryRecord::Blob(Box::new(blob)))?;
+                options.count(|stats| stats.entries_emitted += 1);
+            }
+            continue;
+        }
+
+        // Set when emitting a part failed, to be told apart from a failed read.
+        let mut emit_error: Option<io::Error> = None;
+        let mut parts_written: u64 = 0;
+        let read = match (oversized, options.oversize_policy) {
+            (true, OversizePolicy::Chunk) => {
+                let chunk_len = usize::try_from(options.max_item_size).unwrap_or(usize::MAX);
+                let total_parts = meta
+                    .uncompressed_size
+                    .div_ceil(options.max_item_size.max(1));
+                let mut part = 0;
+                let mut content_type: Option<&str> = None;
+                let mut emit_part = |chunk: &[u8]| -> Result<(), io::Error> {
+                    part += 1;
+                    let mut blob = context.blob(chunk, header_crc32, &mut body_buf)?;
+                    // Detected from the first part only, the others being mid-file.
+                    let first = *content_type.get_or_insert_with(|| context.content_type(chunk));
+                    blob.content_type = Cow::Borrowed(first);
+                    blob.part = Some(Part { part, total_parts });
+                    match options.transformed(blob) {
+                        Some(blob) => {
+                            on_record(EntryRecord::Blob(Box::new(blob)))?;
+                            parts_written += 1;
+                            Ok(())
+                        }
+                        None => Ok(()),
+                    }
+                };
+                handle.read_body_chunks(chunk_len, options.password, &mut |chunk| {
+                    emit_part(chunk).map_err(|e| {
+                        emit_error = Some(e);
+                        io::Error::other("part not emitted")
+                    })
+                })
+            }
+            (true, _) => {
+                handle.read_body_prefix(&mut entry_data, options.max_item_size, options.password)
+            }
+            (false, _) => handle.read_body_with_password(&mut entry_data, options.password),
+        };
+        if let Some(e) = emit_error {
+            // The parts emitted before a limit stopped the zip still count.
+            if parts_written > 0 {
+                options.count(|stats| stats.entries_emitted += 1);
+            }
+            return Err(e);
+        }
+        match read {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                options.report(Diagnostic {
+                    reason: Some("decryption_failed".into()),
+                    item: Some(name.clone()),
+                    error: Some(e.to_string()),
+                    ..Diagnostic::warn("item_skipped", Some(zip_name))
+                })?;
+                on_record(EntryRecord::Error(ErrorRecord {
+                    error: "decryption_failed".into(),
+                    path: zip_name.into(),
+                    item: Some(name),
+                    message: e.to_string(),
+                }))?;
+                continue;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                options.report(Diagnostic {
+                    reason: Some("unsupported_method".into()),
+                    item: Some(name.clone()),
+                    method: Some(meta.compression_method),
+                    ..Diagnostic::warn("item_skipped", Some(zip_name))
+                })?;
+                on_record(EntryRecord::Error(ErrorRecord {
+                    error: "unsupported_method".into(),
+                    path: zip_name.into(),
+                    item: Some(name),
+                    message: e.to_string(),
+                }))?;
+                continue;
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                options.report(Diagnostic {
+                    reason: Some("invalid_data".into()),
+                    item: Some(name.clone()),
+                    error: Some(e.to_string()),
+                    ..Diagnostic::warn("item_skipped", Some(zip_name))
+                })?;
+                on_record(EntryRecord::Error(ErrorRecord {
+                    error: "invalid_entry_data".into(),
+                    path: zip_name.into(),
+                    item: Some(name),
+                    message: e.to_string(),
+                }))?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+        if oversized && options.oversize_policy == OversizePolicy::Chunk {
+            if parts_written > 0 {
+                options.count(|stats| stats.entries_emitted += 1);
+            }
+            continue;
+        }
+        let entry_data = entry_data.as_slice();
+
+        if nested {
+            if let Ok(inner) = zip_entries(entry_data, options) {
+                let inner_prefix = format!("{}!", name);
+                nested_entries2blobs(
+                    zip_name,
+                    &inner_prefix,
+                    depth + 1,
+                    &inner,
+                    options,
+                    listed,
+                    on_record,
+                )?;
+                continue;
+            }
+            // Not a zip after all: converted like any entry.
+            if !glob::is_selected(file_name, options.include, options.exclude) {
+                continue;
+            }
+        }
+
+        let blob = if oversized {
+            options.report(Diagnostic {
+                reason: Some("size_limit_exceeded".into()),
+                item: Some(name.clone()),
+                size: Some(meta.uncompressed_size),
+                ..Diagnostic::warn("item_truncated", Some(zip_name))
+            })?;
+            Blob {
+                truncated: Some(true),
+                ..context.blob(entry_data, header_crc32, &mut body_buf)?
+            }
+        } else {
+            // Computed for the entries whose headers lack it.
+            context.blob(
+                entry_data,
+                header_crc32.or_else(|| Some(rawzip::crc32(entry_data))),
+                &mut body_buf,
+            )?
         };
 
-        serde_json::to_writer(&mut *wtr, &blob)?;
-        writeln!(&mut *wtr)?;
+        if let Some(blob) = options.transformed(blob) {
+            on_record(EntryRecord::Blob(Box::new(blob)))?;
+            options.count(|stats| stats.entries_emitted += 1);
+        }
     }
 
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveRecordMode {
+    /// Only the entries are emitted.
+    #[default]
+    None,
+    /// A record for the zip itself precedes the records of its entries.
+    Also,
+    /// Only the record for the zip itself is emitted.
+    Only,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Checksum {
+    /// Only the CRC-32 of the zip headers.
+    #[default]
+    Crc32,
+    /// Also the SHA-256 of every body.
+    Sha256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyMode {
+    /// The body is encoded inline or stored externally.
+    #[default]
+    Inline,
+    /// Neither `body` nor `body_uri` is emitted; the entries are still read and checked.
+    MetadataOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryOrder {
+    /// The order of the archive (e.g. of the central directory).
+    #[default]
+    Archive,
+    Name,
+    /// By modification time, oldest first.
+    Mtime,
+    /// By uncompressed size, smallest first.
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OversizePolicy {
+    /// The entry is skipped and reported.
+    #[default]
+    Skip,
+    /// The first `max_item_size` bytes are emitted, with `truncated: true`.
+    Truncate,
+    /// The entry is emitted as records of `max_item_size` bytes at most, with
+    /// `part` and `total_parts`.
+    Chunk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Skipped zips and entries are reported and the run goes on.
+    #[default]
+    Lenient,
+    /// The first skipped zip, entry or archive record stops the run with [`Error::Aborted`].
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// One record per entry.
+    #[default]
+    Flat,
+    /// One nested document per archive.
+    Tree,
+}
+
+/// Default `max_zip_size` (1MiB).
+pub const MAX_ZIP_BYTES_DEFAULT: u64 = 1 << 20;
+
+/// Default `max_item_size` (128KiB).
+pub const MAX_ITEM_BYTES_DEFAULT: u64 = 1 << 17;
+
+/// How the zips are converted; [`Options::default`] has the defaults of the command line,
+/// so that the embedders set only the fields they need:
+/// `Options { max_zip_size: 1 << 30, ..Options::default() }`.
 pub struct Options<'a> {
     pub max_zip_size: u64,
     pub content_type: &'a str,
     pub content_encoding: &'a str,
+    /// Entries whose uncompressed size exceeds this are handled by `oversize_policy`.
     pub max_item_size: u64,
+    /// Blobs (parts included) emitted for each input at most, its nested entries included;
+    /// the rest of the input is then skipped with a `zip_truncated` warning.
+    pub max_entries_per_zip: Option<u64>,
+    /// Bytes written by the whole run (partition files and sinks included); once it is
+    /// reached, each input being converted skips the rest of its entries with a
+    /// `zip_truncated` warning, the record crossing it being written whole.
+    pub output_limit: Option<&'a OutputLimit>,
     pub verbose: bool,
+    /// Adds OOXML (docx/xlsx/pptx) core properties to the metadata.
+    pub ooxml: bool,
+    /// Adds the plain text of the OOXML main document parts (requires `ooxml`).
+    pub ooxml_text: bool,
+    /// Adds the JAR manifest main attributes to the metadata of jar/war/ear inputs and nested entries.
+    pub jar_manifest: bool,
+    /// Adds line/byte statistics to the records of text entries.
+    pub text_stats: bool,
+    /// Number of zips read ahead on a background thread while converting (0 disables).
+    pub prefetch: usize,
+    /// Escapes all non-ASCII characters of the output strings as `\uXXXX`.
+    pub ascii_only: bool,
+    /// Content-addressed directory receiving the bodies larger than `external_threshold`.
+    pub external_dir: Option<&'a Path>,
+    /// Bodies up to this size stay inline even when `external_dir` is set.
+    pub external_threshold: u64,
+    pub output_mode: OutputMode,
+    /// Keeps the bodies of the files in `OutputMode::Tree` documents.
+    pub tree_bodies: bool,
+    /// How the inputs read from stdin are specified.
+    pub input_format: InputFormat,
+    /// Processing order of the zips; anything but `AsGiven` reads all inputs first.
+    pub order: InputOrder,
+    pub archive_record: ArchiveRecordMode,
+    pub layout: Layout,
+    /// Adds the named captures of this pattern on the zip path to the metadata.
+    pub path_pattern: Option<&'a PathPattern>,
+    /// Writes the blobs into Hive-style partition files instead of the output stream.
+    ///
+    /// Error records still go to the output stream; `OutputMode::Tree` ignores it.
+    pub partitions: Option<&'a PartitionedOutput>,
+    /// Receives the blobs, e.g. an `HttpSink` (`http-sink` feature), in place of the output
+    /// stream and `partitions`.
+    ///
+    /// Error records still go to the output stream; `OutputMode::Tree` ignores it.
+    pub sink: Option<&'a dyn BlobSink>,
+    /// Reads the entries from the zip files one at a time instead of loading whole zips.
+    ///
+    /// `max_zip_size` then only limits the archive record; `prefetch` is ignored.
+    pub streaming: bool,
+    /// Only the entries matching one of these globs are converted (all when empty).
+    pub include: &'a [Glob],
+    /// Only the entries with these names (`inner.zip!a.txt` for nested ones) are converted,
+    /// those missing being reported as `item_not_found`; set from [`ZipInput::entries`] for
+    /// the inputs listing entries.
+    pub entry_names: Option<&'a [String]>,
+    /// Entries matching one of these globs are not converted.
+    pub exclude: &'a [Glob],
+    /// Number of zips converted at once on worker threads (0 and 1: one at a time).
+    ///
+    /// The records of a zip are still written together and in input order; `prefetch` is ignored.
+    pub jobs: usize,
+    /// How the content type of each entry is chosen; `content_type` is the fallback.
+    pub detect_content_type: ContentTypeDetection,
+    /// How the records are laid out; `JsonArray` brackets the output of `zfilenames2zip2blobs2jsons2writer`
+    /// and `rdr2zip2blobs2jsons2writer`.
+    pub output_format: OutputFormat,
+    /// Transfer encoding of the inline bodies.
+    pub body_encoding: BodyEncoding,
+    /// Checksums added to the entry blobs besides the CRC-32 of the zip headers.
+    pub checksum: Checksum,
+    /// Receives the diagnostics reported with `verbose`; stderr, as text, when `None`.
+    pub diagnostics: Option<&'a dyn DiagnosticSink>,
+    /// Also receives the counts of the functions returning [`RunStats`].
+    pub stats: Option<&'a StatsRecorder>,
+    /// Levels of `.zip` (and `.jar`/`.war`/`.ear`) entries whose own entries are converted in their place (0: none).
+    ///
+    /// Their names are prefixed with the nested zips, as in `inner.zip!a.txt`.
+    pub max_nesting_depth: usize,
+    /// Adds the compression method, Unix mode, attributes and comments of the entries.
+    pub extended_metadata: bool,
+    /// Adds the offset, compressed size and compression method of the data of the entries
+    /// (see [`DataRange`]), to read it again from the zip, e.g. with HTTP range requests.
+    ///
+    /// With `BodyMode::MetadataOnly` the data of the entries is then not read, unless
+    /// `text_stats`, `ooxml_text`, `Checksum::Sha256` or `ContentTypeDetection::Sniff` need it;
+    /// `crc32` is then only set from the headers (none for tar and AES entries).
+    pub offsets: bool,
+    /// Time zone of the DOS times of entries without an extended timestamp field.
+    pub dos_timezone: FixedOffset,
+    /// Password of the encrypted entries; without it they are skipped with `decryption_failed`.
+    pub password: Option<&'a [u8]>,
+    /// Compression of the output of `stdin2zfilenames2zip2blobs2jsons2writer` and
+    /// `dir2zip2blobs2jsons2writer` (partition files excluded); wrap the writer of the
+    /// other functions in a [`CompressedWriter`].
+    pub output_compression: OutputCompression,
+    /// Renames of the keys of the blob records (error and archive records keep theirs).
+    pub field_map: Option<&'a FieldMap>,
+    pub body_mode: BodyMode,
+    /// Receives the progress after each zip of the functions returning [`RunStats`].
+    pub progress: Option<&'a dyn ProgressSink>,
+    /// Applied to each blob (of the entries and the archive records) before it is written.
+    pub transform: Option<&'a dyn BlobTransform>,
+    pub error_policy: ErrorPolicy,
+    pub oversize_policy: OversizePolicy,
+    /// Order of the blobs within each archive; anything but `Archive` buffers the
+    /// entry headers, up to `max_zip_size`.
+    pub entry_order: EntryOrder,
+    /// Codepage of the zip entry names not flagged as UTF-8.
+    pub filename_encoding: FilenameEncoding,
+    /// Zips converted by earlier runs, skipped while unchanged; the zips converted are added to it,
+    /// but not those cut short by `max_entries_per_zip` or `output_limit`, nor the inputs
+    /// converted for the `entry_names` or [`ZipInput::entries`] only.
+    pub checkpoint: Option<&'a Checkpoint>,
+    /// Content-Encoding of the entries matching a rule (the first one), in place of `content_encoding`.
+    pub encoding_rules: &'a [EncodingRule],
+    /// Removes the extension an `encoding_rules` glob matched from the blob names,
+    /// e.g. `a.json.gz` becomes `a.json`.
+    pub strip_encoding_suffix: bool,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Options {
+            max_zip_size: MAX_ZIP_BYTES_DEFAULT,
+            content_type: "application/octet-stream",
+            content_encoding: "identity",
+            max_item_size: MAX_ITEM_BYTES_DEFAULT,
+            max_entries_per_zip: None,
+            output_limit: None,
+            verbose: false,
+            ooxml: false,
+            ooxml_text: false,
+            jar_manifest: false,
+            text_stats: false,
+            prefetch: 0,
+            ascii_only: false,
+            external_dir: None,
+            external_threshold: 0,
+            output_mode: OutputMode::default(),
+            tree_bodies: false,
+            input_format: InputFormat::default(),
+            order: InputOrder::default(),
+            archive_record: ArchiveRecordMode::default(),
+            layout: Layout::default(),
+            path_pattern: None,
+            partitions: None,
+            sink: None,
+            streaming: false,
+            include: &[],
+            entry_names: None,
+            exclude: &[],
+            jobs: 1,
+            detect_content_type: ContentTypeDetection::default(),
+            output_format: OutputFormat::default(),
+            body_encoding: BodyEncoding::default(),
+            checksum: Checksum::default(),
+            diagnostics: None,
+            stats: None,
+            max_nesting_depth: 0,
+            extended_metadata: false,
+            offsets: false,
+            dos_timezone: Utc.fix(),
+            password: None,
+            output_compression: OutputCompression::default(),
+            field_map: None,
+            body_mode: BodyMode::default(),
+            progress: None,
+            transform: None,
+            error_policy: ErrorPolicy::default(),
+            oversize_policy: OversizePolicy::default(),
+            entry_order: EntryOrder::default(),
+            filename_encoding: FilenameEncoding::default(),
+            checkpoint: None,
+            encoding_rules: &[],
+            strip_encoding_suffix: false,
+        }
+    }
+}
+
+impl Options<'_> {
+    // Fails with `Error::Aborted` for the skips of `ErrorPolicy::Strict`.
+    fn report(&self, diagnostic: Diagnostic) -> Result<(), io::Error> {
+        if let Some(stats) = self.stats {
+            stats.count_diagnostic(&diagnostic);
+        }
+        if self.verbose {
+            match self.diagnostics {
+                Some(sink) => sink.report(&diagnostic),
+                None => eprintln!("{}", diagnostic),
+            }
+        }
+        if self.error_policy == ErrorPolicy::Strict && diagnostic.is_skip() {
+            return Err(Error::Aborted(Box::new(diagnostic)).into());
+        }
+        Ok(())
+    }
+
+    // Reports the error that stopped the conversion of a zip, unless it is an abort.
+    fn report_unrecoverable(&self, e: io::Error, path: Option<&str>) -> Result<(), io::Error> {
+        if error::is_aborted(&e) {
+            return Err(e);
+        }
+        self.report(Diagnostic {
+            reason: Some(e.to_string()),
+            ..Diagnostic::warn("unrecoverable_error", path)
+        })
+    }
+
+    fn count<F>(&self, f: F)
+    where
+        F: FnOnce(&mut RunStats),
+    {
+        if let Some(stats) = self.stats {
+            stats.update(f);
+        }
+    }
+
+    // The blob `transform` makes of `blob`, `None` when it drops it.
+    fn transformed<'b>(&self, blob: Blob<'b>) -> Option<Blob<'b>> {
+        let Some(transform) = self.transform else {
+            return Some(blob);
+        };
+        let transformed = transform.transform(blob);
+        if transformed.is_none() {
+            self.count(|stats| stats.blobs_dropped += 1);
+        }
+        transformed
+    }
+
+    // Counts a zip whose records were written, recording it in the checkpoint.
+    fn zip_processed(&self, zip_name: &str) {
+        self.count(|stats| stats.zips_processed += 1);
+        if let Some(checkpoint) = self.checkpoint {
+            checkpoint.finish(zip_name);
+        }
+    }
+
+    // Whether the checkpoint has the input as converted and unchanged since.
+    //
+    // Inputs converted for some of their entries only are neither skipped nor recorded.
+    fn unchanged(&self, input: &ZipInput) -> bool {
+        let Some(checkpoint) = self.checkpoint else {
+            return false;
+        };
+        if input.entries.is_some() || self.entry_names.is_some() {
+            return false;
+        }
+        // Unreadable inputs are reported by the conversion.
+        let Ok(stamp) = FileStamp::of(&input.path) else {
+            return false;
+        };
+        if checkpoint.is_done(&input.path, &stamp) {
+            self.count(|stats| stats.zips_unchanged += 1);
+            return true;
+        }
+        checkpoint.start(&input.path, stamp);
+        false
+    }
+
+    // Why no more blobs may be emitted for a zip after `blobs` of them, if so.
+    fn limit_reached(&self, blobs: u64) -> Option<&'static str> {
+        if self.max_entries_per_zip.is_some_and(|max| blobs >= max) {
+            Some("entry_limit_exceeded")
+        } else if self.output_limit.is_some_and(OutputLimit::is_reached) {
+            Some("output_limit_exceeded")
+        } else {
+            None
+        }
+    }
+
+    // `self` converting only the entries `input` lists, if any.
+    fn listing<'b>(&'b self, input: &'b ZipInput) -> Options<'b> {
+        Options {
+            entry_names: input.entries.as_deref().or(self.entry_names),
+            ..*self
+        }
+    }
+
+    // Whether the blobs of the entries can be made from their headers alone, for an index.
+    fn headers_suffice(&self) -> bool {
+        self.offsets
+            && self.body_mode == BodyMode::MetadataOnly
+            && !self.text_stats
+            && !self.ooxml_text
+            && self.checksum == Checksum::Crc32
+            && self.detect_content_type != ContentTypeDetection::Sniff
+    }
+
+    // Reports the progress once a zip is converted or skipped.
+    fn zip_done(&self) {
+        if let (Some(progress), Some(stats)) = (self.progress, self.stats) {
+            progress.progress(&Progress::from(&stats.snapshot()));
+        }
+    }
 }
 
 pub fn zfilename2zip2blobs2jsons2writer<P, W>(
@@ -164,58 +1591,414 @@ pub fn zfilename2zip2blobs2jsons2writer<P, W>(
     buf: &mut Vec<u8>,
     options: &Options,
     wtr: &mut BufWriter<W>,
-) -> Result<(), io::Error>
+) -> Result<(), Error>
 where
     W: Write,
     P: AsRef<Path> + Clone,
 {
-    let zfn_for_err = zfilename.as_ref().to_string_lossy().to_string();
-    match filename2buf(zfilename.as_ref(), buf, options.max_zip_size) {
-        Ok(_) => {
-            // Processing continues below
+    let input = ZipInput::from(zfilename.as_ref().to_string_lossy().to_string());
+    zinput2zip2blobs2jsons2writer(&input, buf, options, wtr).map_err(Error::from)
+}
+
+fn zinput2zip2blobs2jsons2writer<W>(
+    input: &ZipInput,
+    buf: &mut Vec<u8>,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    // The few entries listed are read from the file instead of the whole zip.
+    if options.streaming || input.entries.is_some() {
+        return Ok(zfile2zip2blobs2jsons2writer_streaming(
+            input, buf, options, wtr,
+        )?);
+    }
+    let loaded = filename2buf(&input.path, buf, options.max_zip_size);
+    loaded2zip2blobs2jsons2writer(input, &loaded, buf, options, wtr)
+}
+
+// Checks the digest and the format of an input before its entries are read.
+//
+// Returns the format of the archive (zip, tar, gzip or zstd), or `None` when the input is skipped.
+fn accept_archive<D, W>(
+    input: &ZipInput,
+    digest: D,
+    head: &[u8],
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<Option<ArchiveFormat>, io::Error>
+where
+    D: FnOnce() -> Result<String, io::Error>,
+    W: Write,
+{
+    let zip_name = input.path.as_str();
+    if let Some(expected) = &input.sha256 {
+        let actual = digest()?;
+        if !expected.eq_ignore_ascii_case(&actual) {
+            options.report(Diagnostic {
+                reason: Some("digest_mismatch".into()),
+                expected: Some(expected.clone()),
+                actual: Some(actual.clone()),
+                ..Diagnostic::warn("zip_skipped", Some(zip_name))
+            })?;
+            let record = ErrorRecord {
+                error: "digest_mismatch".into(),
+                path: zip_name.into(),
+                item: None,
+                message: format!("sha256 mismatch: expected {}, actual {}", expected, actual),
+            };
+            error_record2writer(&record, options, wtr)?;
+            return Ok(None);
+        }
+    }
+
+    let format = match detect_format(head) {
+        format @ (ArchiveFormat::Zip | ArchiveFormat::Tar | ArchiveFormat::Gzip) => format,
+        #[cfg(feature = "zstd")]
+        ArchiveFormat::Zstd => ArchiveFormat::Zstd,
+        #[cfg(not(feature = "zstd"))]
+        ArchiveFormat::Zstd => {
+            options.report(Diagnostic {
+                reason: Some("unsupported_format".into()),
+                format: Some(ArchiveFormat::Zstd.to_string()),
+                error: Some("built without the zstd feature".into()),
+                ..Diagnostic::warn("zip_skipped", Some(zip_name))
+            })?;
+            return Ok(None);
+        }
+        // Old tars have no magic.
+        ArchiveFormat::Unknown if format::is_tar_name(zip_name) => ArchiveFormat::Tar,
+        ArchiveFormat::Unknown => {
+            // Zips with a prefix (e.g. self-extracting) are still located by their end record.
+            options.report(Diagnostic::warn("format_unknown", Some(zip_name)))?;
+            ArchiveFormat::Zip
+        }
+        format => {
+            options.report(Diagnostic {
+                reason: Some("unsupported_format".into()),
+                format: Some(format.to_string()),
+                ..Diagnostic::warn("zip_skipped", Some(zip_name))
+            })?;
+            return Ok(None);
         }
+    };
+
+    if let Some(pattern) = options.path_pattern
+        && pattern.captures(zip_name).is_none()
+    {
+        options.report(Diagnostic::warn("path_pattern_unmatched", Some(zip_name)))?;
+    }
+    Ok(Some(format))
+}
+
+/// Converts the zip at `input.path` reading its entries from the file one at a time.
+///
+/// Only the entry being converted is held in memory, so `max_zip_size` does not
+/// limit the zip; it still limits the archive record, which needs the whole zip in `buf`.
+pub fn zfile2zip2blobs2jsons2writer_streaming<W>(
+    input: &ZipInput,
+    buf: &mut Vec<u8>,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    let options = &options.listing(input);
+    let zip_name = input.path.as_str();
+    let mut file = match File::open(zip_name) {
+        Ok(file) => file,
         Err(e) => {
-            if options.verbose {
-                match e {
-                    ReadError::SizeLimitExceeded => {
-                        eprintln!(
-                            "level:warn\tstatus:zip_skipped\treason:size_limit_exceeded\tpath:{}",
-                            zfn_for_err
-                        );
-                    }
-                    ReadError::Io(io_err) => {
-                        eprintln!(
-                            "level:warn\tstatus:zip_skipped\treason:read_error\tpath:{}\terror:{}",
-                            zfn_for_err, io_err
-                        );
-                    }
+            options.report(Diagnostic {
+                reason: Some("read_error".into()),
+                error: Some(e.to_string()),
+                ..Diagnostic::warn("zip_skipped", Some(zip_name))
+            })?;
+            return Ok(());
+        }
+    };
+
+    let mut head = Vec::with_capacity(format::HEAD_LEN);
+    (&mut file)
+        .take(format::HEAD_LEN as u64)
+        .read_to_end(&mut head)?;
+    let digest = || {
+        file.seek(SeekFrom::Start(0))?;
+        external::sha256_hex_reader(&mut file)
+    };
+    let Some(format) = accept_archive(input, digest, &head, options, wtr)? else {
+        return Ok(());
+    };
+    let size = file.metadata()?.len();
+    options.count(|stats| stats.bytes_in += size);
+
+    if format != ArchiveFormat::Zip {
+        // Tars are read whole, as without `streaming`.
+        return match filename2buf(zip_name, buf, options.max_zip_size) {
+            Ok(()) => Ok(accepted2zip2blobs2jsons2writer(
+                input, format, buf, options, wtr,
+            )?),
+            Err(ReadError::SizeLimitExceeded) => {
+                options.report(Diagnostic {
+                    reason: Some("size_limit_exceeded".into()),
+                    ..Diagnostic::warn("zip_skipped", Some(zip_name))
+                })?;
+                Ok(())
+            }
+            Err(ReadError::Io(e)) => Err(Error::from(e)),
+        };
+    }
+
+    if options.archive_record != ArchiveRecordMode::None {
+        match filename2buf(zip_name, buf, options.max_zip_size) {
+            Ok(()) => {
+                let blob = archive2blob(zip_name, buf, options)?;
+                if let Some(blob) = options.transformed(blob) {
+                    blob2writer(&blob, options, wtr)?;
                 }
             }
-            return Ok(()); // Skip to the next file
+            Err(ReadError::SizeLimitExceeded) => {
+                options.report(Diagnostic {
+                    reason: Some("size_limit_exceeded".into()),
+                    ..Diagnostic::warn("archive_record_skipped", Some(zip_name))
+                })?;
+            }
+            Err(ReadError::Io(e)) => return Err(Error::from(e)),
         }
+        if options.archive_record == ArchiveRecordMode::Only {
+            options.zip_processed(zip_name);
+            return Ok(());
+        }
+    }
+
+    let converted = ArchiveEntries::from_file(file)
+        .map(|entries| entries.with_filename_encoding(options.filename_encoding))
+        .and_then(|entries| entries2zip2blobs2jsons2writer(zip_name, &entries, options, wtr));
+    match converted {
+        Ok(()) => options.zip_processed(zip_name),
+        Err(e) if error::is_aborted(&e) => return Err(Error::from(e)),
+        Err(e) => options.report(Diagnostic {
+            reason: Some(e.to_string()),
+            ..Diagnostic::warn("zip_processing_failed", Some(zip_name))
+        })?,
+    }
+    Ok(())
+}
+
+// Converts a zip already read into `buf` (or reports why it could not be read).
+fn loaded2zip2blobs2jsons2writer<W>(
+    input: &ZipInput,
+    loaded: &Result<(), ReadError>,
+    buf: &[u8],
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    let options = &options.listing(input);
+    let zip_name = input.path.as_str();
+    if let Err(e) = loaded {
+        match e {
+            ReadError::SizeLimitExceeded => {
+                options.report(Diagnostic {
+                    reason: Some("size_limit_exceeded".into()),
+                    ..Diagnostic::warn("zip_skipped", Some(zip_name))
+                })?;
+            }
+            ReadError::Io(io_err) => {
+                options.report(Diagnostic {
+                    reason: Some("read_error".into()),
+                    error: Some(io_err.to_string()),
+                    ..Diagnostic::warn("zip_skipped", Some(zip_name))
+                })?;
+            }
+        }
+        return Ok(()); // Skip to the next file
+    }
+    options.count(|stats| stats.bytes_in += buf.len() as u64);
+
+    let digest = || Ok(external::sha256_hex(buf));
+    let Some(format) = accept_archive(input, digest, buf, options, wtr)? else {
+        return Ok(());
     };
+    accepted2zip2blobs2jsons2writer(input, format, buf, options, wtr)
+}
 
-    let zip_name = zfilename.as_ref().to_string_lossy().to_string();
-
-    if let Err(e) = buf2zip2blobs2jsons2writer(
-        &zip_name,
-        buf,
-        options.content_type,
-        options.content_encoding,
-        options.max_item_size,
-        options.verbose,
-        wtr,
-    ) && options.verbose
-    {
-        eprintln!(
-            "level:warn\tstatus:zip_processing_failed\tpath:{}\treason:{}",
-            zfn_for_err, e
-        );
+// Converts an accepted archive of `format` read into `buf`.
+fn accepted2zip2blobs2jsons2writer<W>(
+    input: &ZipInput,
+    format: ArchiveFormat,
+    buf: &[u8],
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    let zip_name = input.path.as_str();
+    if options.archive_record != ArchiveRecordMode::None {
+        let blob = archive2blob(zip_name, buf, options)?;
+        if let Some(blob) = options.transformed(blob) {
+            blob2writer(&blob, options, wtr)?;
+        }
+        if options.archive_record == ArchiveRecordMode::Only {
+            options.zip_processed(zip_name);
+            return Ok(());
+        }
+    }
+
+    let mut decompressed = Vec::new();
+    let converted = match (format, decompress::decompressed_input(format, buf)) {
+        (_, Some(decompressor)) => {
+            match rdr2buf(decompressor, &mut decompressed, options.max_zip_size) {
+                Ok(()) => {
+                    // Offsets into the decompressed archive would locate nothing in the input.
+                    let inner_options = &Options {
+                        offsets: false,
+                        ..*options
+                    };
+                    match detect_format(&decompressed) {
+                        ArchiveFormat::Zip => {
+                            buf2zip2blobs2jsons2writer(zip_name, &decompressed, inner_options, wtr)
+                        }
+                        ArchiveFormat::Tar => {
+                            buf2tar2blobs2jsons2writer(zip_name, &decompressed, inner_options, wtr)
+                        }
+                        ArchiveFormat::Unknown if format::is_tar_name(zip_name) => {
+                            buf2tar2blobs2jsons2writer(zip_name, &decompressed, inner_options, wtr)
+                        }
+                        inner => {
+                            options.report(Diagnostic {
+                                reason: Some("unsupported_format".into()),
+                                format: Some(format!("{}+{}", format, inner)),
+                                ..Diagnostic::warn("zip_skipped", Some(zip_name))
+                            })?;
+                            return Ok(());
+                        }
+                    }
+                }
+                Err(ReadError::SizeLimitExceeded) => {
+                    options.report(Diagnostic {
+                        reason: Some("size_limit_exceeded".into()),
+                        ..Diagnostic::warn("zip_skipped", Some(zip_name))
+                    })?;
+                    return Ok(());
+                }
+                Err(ReadError::Io(e)) => Err(Error::from(e)),
+            }
+        }
+        (ArchiveFormat::Tar, None) => buf2tar2blobs2jsons2writer(zip_name, buf, options, wtr),
+        (_, None) => buf2zip2blobs2jsons2writer(zip_name, buf, options, wtr),
+    };
+    match converted {
+        Ok(()) => options.zip_processed(zip_name),
+        Err(aborted @ Error::Aborted(_)) => return Err(aborted.into()),
+        Err(e) => options.report(Diagnostic {
+            reason: Some(e.to_string()),
+            ..Diagnostic::warn("zip_processing_failed", Some(zip_name))
+        })?,
     }
     Ok(())
 }
 
-pub fn zfilenames2zip2blobs2jsons2writer<I, W>(
+/// `ZipName` of the zip read from stdin with `InputFormat::Bytes`.
+pub const STDIN_ZIP_NAME: &str = "-";
+
+/// Converts the zip archive read from `rdr` (e.g. a pipe), named `zip_name` in the records.
+///
+/// The whole zip is read into `buf`, up to `max_zip_size`, even with `streaming`.
+pub fn rdr2zip2blobs2jsons2writer<R, W>(
+    zip_name: &str,
+    rdr: R,
+    buf: &mut Vec<u8>,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<RunStats, Error>
+where
+    R: Read,
+    W: Write,
+{
+    with_stats(options, |options| {
+        let input = ZipInput::from(zip_name.to_string());
+        let loaded = rdr2buf(rdr, buf, options.max_zip_size);
+        if options.output_format == OutputFormat::JsonArray {
+            let mut array = BufWriter::new(JsonArrayWriter::new(&mut *wtr));
+            loaded2zip2blobs2jsons2writer(&input, &loaded, buf, options, &mut array)?;
+            array.into_inner().map_err(|e| e.into_error())?.finish()?;
+            return Ok(());
+        }
+        loaded2zip2blobs2jsons2writer(&input, &loaded, buf, options, wtr)
+    })
+    .map_err(Error::from)
+}
+
+// Runs `convert` counting into a recorder of its own, returning the counts
+// (also added to `options.stats`, if any).
+fn with_stats<F>(options: &Options, convert: F) -> Result<RunStats, io::Error>
+where
+    F: FnOnce(&Options) -> Result<(), io::Error>,
+{
+    let start = Instant::now();
+    let recorder = StatsRecorder::default();
+    convert(&Options {
+        stats: Some(&recorder),
+        ..*options
+    })?;
+    let stats = recorder.finish(start.elapsed());
+    if let Some(outer) = options.stats {
+        outer.add(&stats);
+    }
+    Ok(stats)
+}
+
+pub fn zfilenames2zip2blobs2jsons2writer<I, T, W>(
+    zfilenames: I,
+    buf: &mut Vec<u8>,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<RunStats, Error>
+where
+    W: Write,
+    I: Iterator<Item = Result<T, io::Error>>,
+    T: Into<ZipInput>,
+{
+    with_stats(options, |options| {
+        let zinputs = zfilenames
+            .map(|res| res.map(Into::into))
+            .filter(|res| !matches!(res, Ok(input) if options.unchanged(input)));
+        if options.output_format == OutputFormat::JsonArray {
+            let mut array = BufWriter::new(JsonArrayWriter::new(&mut *wtr));
+            ordered2zip2blobs2jsons2writer(zinputs, buf, options, &mut array)?;
+            array.into_inner().map_err(|e| e.into_error())?.finish()?;
+            return Ok(());
+        }
+        ordered2zip2blobs2jsons2writer(zinputs, buf, options, wtr)
+    })
+    .map_err(Error::from)
+}
+
+// Converts the inputs in `options.order`.
+fn ordered2zip2blobs2jsons2writer<I, W>(
+    zinputs: I,
+    buf: &mut Vec<u8>,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), io::Error>
+where
+    W: Write,
+    I: Iterator<Item = Result<ZipInput, io::Error>>,
+{
+    if options.order != InputOrder::AsGiven {
+        let ordered = order_zinputs(zinputs, options.order);
+        return zinputs2zip2blobs2jsons2writer(ordered.into_iter(), buf, options, wtr);
+    }
+    zinputs2zip2blobs2jsons2writer(zinputs, buf, options, wtr)
+}
+
+fn zinputs2zip2blobs2jsons2writer<I, W>(
     zfilenames: I,
     buf: &mut Vec<u8>,
     options: &Options,
@@ -223,49 +2006,155 @@ pub fn zfilenames2zip2blobs2jsons2writer<I, W>(
 ) -> Result<(), io::Error>
 where
     W: Write,
-    I: Iterator<Item = Result<String, io::Error>>,
+    I: Iterator<Item = Result<ZipInput, io::Error>>,
 {
+    if options.jobs > 1 {
+        return parallel::zinputs2writer_parallel(zfilenames, options.jobs, options, wtr);
+    }
+    if options.prefetch > 0 && !options.streaming {
+        return prefetch::for_each_prefetched(
+            zfilenames,
+            options.prefetch,
+            options.max_zip_size,
+            |loaded_res| {
+                match loaded_res {
+                    Ok(loaded) => {
+                        if let Err(e) = loaded2zip2blobs2jsons2writer(
+                            &loaded.input,
+                            &loaded.result,
+                            &loaded.buf,
+                            options,
+                            wtr,
+                        ) {
+                            options.report_unrecoverable(e, Some(loaded.input.path.as_str()))?;
+                        }
+                    }
+                    Err(e) => options.report_unrecoverable(e, None)?,
+                }
+                options.zip_done();
+                Ok(())
+            },
+        );
+    }
+
     for zfilename_res in zfilenames {
         match zfilename_res {
-            Ok(zfilename) => {
-                if let Err(e) = zfilename2zip2blobs2jsons2writer(&zfilename, buf, options, wtr)
-                    && options.verbose
-                {
-                    eprintln!(
-                        "level:warn\tstatus:unrecoverable_error\tpath:{}\treason:{}",
-                        zfilename, e
-                    );
-                }
-            }
-            Err(e) => {
-                if options.verbose {
-                    eprintln!("level:warn\tstatus:unrecoverable_error\treason:{}", e);
+            Ok(input) => {
+                if let Err(e) = zinput2zip2blobs2jsons2writer(&input, buf, options, wtr) {
+                    options.report_unrecoverable(e, Some(input.path.as_str()))?;
                 }
             }
+            Err(e) => options.report_unrecoverable(e, None)?,
         }
+        options.zip_done();
     }
     Ok(())
 }
 
-pub fn stdin2zfilenames2zip2blobs2jsons2stdout(
-    max_zip_size: u64,
-    content_type: &str,
-    content_encoding: &str,
-    max_item_size: u64,
-    verbose: bool,
-) -> Result<(), io::Error> {
-    let stdout = io::stdout();
-    let mut writer = BufWriter::new(stdout.lock());
+pub fn stdin2zfilenames2zip2blobs2jsons2stdout(options: &Options) -> Result<RunStats, Error> {
+    stdin2zfilenames2zip2blobs2jsons2writer(options, io::stdout().lock())
+}
+
+/// Converts the zips named on stdin (in `options.input_format`), writing to `wtr`,
+/// e.g. a [`RotatingWriter`].
+pub fn stdin2zfilenames2zip2blobs2jsons2writer<W>(
+    options: &Options,
+    wtr: W,
+) -> Result<RunStats, Error>
+where
+    W: Write,
+{
+    let mut writer = BufWriter::new(CompressedWriter::new(options.output_compression, wtr)?);
     let mut buf: Vec<u8> = Vec::with_capacity((1 << 20) * 2);
-    let options = Options {
-        max_zip_size,
-        content_type,
-        content_encoding,
-        max_item_size,
-        verbose,
+
+    let stats = match options.input_format {
+        InputFormat::Lines => {
+            zfilenames2zip2blobs2jsons2writer(stdin2filenames(), &mut buf, options, &mut writer)?
+        }
+        InputFormat::Jsonl => zfilenames2zip2blobs2jsons2writer(
+            rdr2zinputs(io::stdin().lock()),
+            &mut buf,
+            options,
+            &mut writer,
+        )?,
+        InputFormat::Entries => zfilenames2zip2blobs2jsons2writer(
+            rdr2zentries(io::stdin().lock()),
+            &mut buf,
+            options,
+            &mut writer,
+        )?,
+        InputFormat::Bytes => rdr2zip2blobs2jsons2writer(
+            STDIN_ZIP_NAME,
+            io::stdin().lock(),
+            &mut buf,
+            options,
+            &mut writer,
+        )?,
     };
 
-    zfilenames2zip2blobs2jsons2writer(stdin2filenames(), &mut buf, &options, &mut writer)?;
+    if let Some(partitions) = options.partitions {
+        partitions.flush()?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.finish()?;
+    Ok(stats)
+}
+
+/// Converts the zips found under `root` (see [`dir2zfilenames`]), writing to stdout.
+pub fn dir2zip2blobs2jsons2stdout(
+    root: &Path,
+    extensions: &[String],
+    options: &Options,
+) -> Result<RunStats, Error> {
+    dir2zip2blobs2jsons2writer(root, extensions, options, io::stdout().lock())
+}
+
+/// Converts the zips found under `root`, writing to `wtr`.
+pub fn dir2zip2blobs2jsons2writer<W>(
+    root: &Path,
+    extensions: &[String],
+    options: &Options,
+    wtr: W,
+) -> Result<RunStats, Error>
+where
+    W: Write,
+{
+    let mut writer = BufWriter::new(CompressedWriter::new(options.output_compression, wtr)?);
+    let mut buf: Vec<u8> = Vec::with_capacity((1 << 20) * 2);
+
+    let stats = zfilenames2zip2blobs2jsons2writer(
+        dir2zfilenames(root, extensions),
+        &mut buf,
+        options,
+        &mut writer,
+    )?;
+
+    if let Some(partitions) = options.partitions {
+        partitions.flush()?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.finish()?;
+    Ok(stats)
+}
+
+/// Converts the entries listed by the manifest at `path`, in [`InputFormat::Entries`] lines,
+/// writing to `wtr`.
+pub fn manifest2zip2blobs2jsons2writer<W>(
+    path: &Path,
+    options: &Options,
+    wtr: W,
+) -> Result<RunStats, Error>
+where
+    W: Write,
+{
+    let manifest = BufReader::new(File::open(path)?);
+    let mut writer = BufWriter::new(CompressedWriter::new(options.output_compression, wtr)?);
+    let mut buf: Vec<u8> = Vec::with_capacity((1 << 20) * 2);
+
+    let stats =
+        zfilenames2zip2blobs2jsons2writer(rdr2zentries(manifest), &mut buf, options, &mut writer)?;
 
-    writer.flush()
+    if let Some(partitions) = options.partitions {
+        partitions.flush()?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.finish()?;
+    Ok(stats)
 }
@@ -0,0 +1,38 @@
+// A cap on the output of a whole run, shared by the worker threads.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counts the bytes of the records written against `max_bytes`, for
+/// `Options::output_limit`.
+#[derive(Debug)]
+pub struct OutputLimit {
+    max_bytes: u64,
+    written: AtomicU64,
+}
+
+impl OutputLimit {
+    pub fn new(max_bytes: u64) -> Self {
+        OutputLimit {
+            max_bytes,
+            written: AtomicU64::new(0),
+        }
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Bytes of the records written so far.
+    pub fn written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Whether no more records may be written.
+    pub fn is_reached(&self) -> bool {
+        self.written() >= self.max_bytes
+    }
+
+    pub(crate) fn add(&self, bytes: u64) {
+        self.written.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,137 @@
+// Content-type detection of entries from their extension and leading bytes.
+
+/// How the content type of an entry is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentTypeDetection {
+    /// Every entry gets `Options::content_type`.
+    #[default]
+    None,
+    /// From the file extension, falling back to `Options::content_type`.
+    Extension,
+    /// From the extension, then magic bytes, then whether the data is UTF-8 text.
+    Sniff,
+}
+
+const TEXT_PLAIN: &str = "text/plain; charset=utf-8";
+
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("7z", "application/x-7z-compressed"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("bz2", "application/x-bzip2"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("ear", "application/java-archive"),
+    ("epub", "application/epub+zip"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("jar", "application/java-archive"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("jsonl", "application/jsonl"),
+    ("md", "text/markdown"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("ndjson", "application/x-ndjson"),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ogg", "audio/ogg"),
+    ("parquet", "application/vnd.apache.parquet"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("rtf", "application/rtf"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("toml", "application/toml"),
+    ("tsv", "text/tab-separated-values"),
+    ("txt", TEXT_PLAIN),
+    ("war", "application/java-archive"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webp", "image/webp"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("zip", "application/zip"),
+    ("zst", "application/zstd"),
+];
+
+const MAGICS: &[(&[u8], &str)] = &[
+    (b"%PDF-", "application/pdf"),
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"BZh", "application/x-bzip2"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"\x00asm", "application/wasm"),
+    (b"PAR1", "application/vnd.apache.parquet"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+    (b"{\\rtf", "application/rtf"),
+];
+
+/// Content type registered for the extension of `name`, if any.
+pub fn from_extension(name: &str) -> Option<&'static str> {
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    let (_, ext) = file_name.rsplit_once('.')?;
+    let ext = ext.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(known, _)| *known == ext)
+        .map(|(_, mime)| *mime)
+}
+
+/// Content type announced by the leading bytes of `data`, if any.
+pub fn from_magic(data: &[u8]) -> Option<&'static str> {
+    if let Some((_, mime)) = MAGICS.iter().find(|(magic, _)| data.starts_with(magic)) {
+        return Some(mime);
+    }
+    // RIFF containers carry their type at offset 8.
+    match (data.get(..4), data.get(8..12)) {
+        (Some(b"RIFF"), Some(b"WEBP")) => Some("image/webp"),
+        (Some(b"RIFF"), Some(b"WAVE")) => Some("audio/wav"),
+        _ => None,
+    }
+}
+
+/// Content type of an entry named `name` holding `data`, or `None` for the default.
+pub fn detect(mode: ContentTypeDetection, name: &str, data: &[u8]) -> Option<&'static str> {
+    if name.ends_with('/') {
+        return None;
+    }
+    match mode {
+        ContentTypeDetection::None => None,
+        ContentTypeDetection::Extension => from_extension(name),
+        ContentTypeDetection::Sniff => from_extension(name)
+            .or_else(|| from_magic(data))
+            .or_else(|| crate::text::is_text(data).then_some(TEXT_PLAIN)),
+    }
+}
@@ -0,0 +1,170 @@
+// Minimal OOXML (docx/xlsx/pptx) support: core properties and plain text.
+//
+// The documents are well-formed XML produced by office suites, so a small
+// tag scanner is sufficient; no general purpose XML parser is pulled in.
+
+use std::collections::BTreeMap;
+
+pub const CONTENT_TYPES_PATH: &str = "[Content_Types].xml";
+pub const CORE_PROPERTIES_PATH: &str = "docProps/core.xml";
+
+// (local element name, metadata key)
+const CORE_PROPERTIES: &[(&str, &str)] = &[
+    ("title", "OoxmlTitle"),
+    ("creator", "OoxmlAuthor"),
+    ("created", "OoxmlCreated"),
+    ("modified", "OoxmlModified"),
+];
+
+// Returns the (text element, paragraph element) local names for a main part.
+fn text_elements(part_name: &str) -> Option<(&'static str, &'static str)> {
+    if part_name == "word/document.xml" {
+        return Some(("t", "p"));
+    }
+    if part_name == "xl/sharedStrings.xml" {
+        return Some(("t", "si"));
+    }
+    if part_name.starts_with("ppt/slides/slide") && part_name.ends_with(".xml") {
+        return Some(("t", "p"));
+    }
+    None
+}
+
+pub fn is_text_part(part_name: &str) -> bool {
+    text_elements(part_name).is_some()
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                .and_then(|r| r.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Splits a tag body like `w:t xml:space="preserve"` into its local name.
+fn local_name(tag: &str) -> &str {
+    let qname = tag
+        .split(|c: char| c.is_ascii_whitespace() || c == '/')
+        .next()
+        .unwrap_or_default();
+    qname.rsplit(':').next().unwrap_or(qname)
+}
+
+enum Event<'a> {
+    Start(&'a str),
+    End(&'a str),
+    Text(&'a str),
+}
+
+fn events(xml: &str) -> impl Iterator<Item = Event<'_>> {
+    let mut rest = xml;
+    let mut pending_end: Option<&str> = None;
+    std::iter::from_fn(move || {
+        if let Some(name) = pending_end.take() {
+            return Some(Event::End(name));
+        }
+        loop {
+            if rest.is_empty() {
+                return None;
+            }
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                let text = &rest[..end];
+                rest = &rest[end..];
+                return Some(Event::Text(text));
+            }
+            let close = rest.find('>')?;
+            let tag = &rest[1..close];
+            rest = &rest[close + 1..];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Event::End(local_name(name.trim())));
+            }
+            let name = local_name(tag);
+            if tag.ends_with('/') {
+                pending_end = Some(name);
+            }
+            return Some(Event::Start(name));
+        }
+    })
+}
+
+/// Extracts the core properties (title, author, created, modified).
+pub fn core_properties(xml: &str) -> BTreeMap<String, String> {
+    let mut props = BTreeMap::new();
+    let mut current: Option<&str> = None;
+    for event in events(xml) {
+        match event {
+            Event::Start(name) => {
+                current = CORE_PROPERTIES
+                    .iter()
+                    .find(|(element, _)| *element == name)
+                    .map(|(_, key)| *key);
+            }
+            Event::Text(text) => {
+                if let Some(key) = current {
+                    let value = unescape(text.trim());
+                    if !value.is_empty() {
+                        props.insert(key.to_string(), value);
+                    }
+                }
+            }
+            Event::End(_) => current = None,
+        }
+    }
+    props
+}
+
+/// Extracts the plain text of a main document part (document, shared strings or slide).
+pub fn part_text(part_name: &str, xml: &str) -> Option<String> {
+    let (text_element, paragraph_element) = text_elements(part_name)?;
+    let mut out = String::new();
+    let mut in_text = false;
+    for event in events(xml) {
+        match event {
+            Event::Start(name) => in_text = name == text_element,
+            Event::Text(text) if in_text => out.push_str(&unescape(text)),
+            Event::Text(_) => {}
+            Event::End(name) => {
+                in_text = false;
+                if name == paragraph_element {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    Some(out)
+}
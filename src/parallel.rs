@@ -0,0 +1,117 @@
+// Converts several zips at once on worker threads, writing their records in input order.
+
+use crate::{Options, ZipInput, zinput2zip2blobs2jsons2writer};
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+
+// Converts one zip into the records it produces, reporting failures like the sequential path.
+fn convert(input: &ZipInput, buf: &mut Vec<u8>, options: &Options) -> Result<Vec<u8>, io::Error> {
+    let mut out = BufWriter::new(Vec::new());
+    if let Err(e) = zinput2zip2blobs2jsons2writer(input, buf, options, &mut out) {
+        options.report_unrecoverable(e, Some(input.path.as_str()))?;
+    }
+    options.zip_done();
+    let (mut records, buffered) = out.into_parts();
+    if let Ok(buffered) = buffered {
+        records.extend_from_slice(&buffered);
+    }
+    Ok(records)
+}
+
+/// Converts the inputs on `jobs` threads.
+///
+/// The records of each zip are collected by its worker and written to `wtr` in
+/// one piece, in the order of the inputs. Falls back to converting on the
+/// calling thread when threads are unavailable (e.g. wasm32-wasip1).
+pub fn zinputs2writer_parallel<I, W>(
+    zinputs: I,
+    jobs: usize,
+    options: &Options,
+    wtr: &mut BufWriter<W>,
+) -> Result<(), io::Error>
+where
+    I: Iterator<Item = Result<ZipInput, io::Error>>,
+    W: Write,
+{
+    let mut zinputs = zinputs.fuse();
+    thread::scope(|scope| {
+        let (job_tx, job_rx) = mpsc::channel::<(usize, ZipInput)>();
+        let (done_tx, done_rx) = mpsc::channel::<(usize, Result<Vec<u8>, io::Error>)>();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let mut workers = 0;
+        for _ in 0..jobs {
+            let job_rx = Arc::clone(&job_rx);
+            let done_tx = done_tx.clone();
+            let spawned =
+                thread::Builder::new()
+                    .name("convert".into())
+                    .spawn_scoped(scope, move || {
+                        let mut buf = Vec::new();
+                        loop {
+                            let job = match job_rx.lock() {
+                                Ok(rx) => rx.recv(),
+                                Err(_) => break,
+                            };
+                            let Ok((ix, input)) = job else { break };
+                            let records = convert(&input, &mut buf, options);
+                            if done_tx.send((ix, records)).is_err() {
+                                break;
+                            }
+                        }
+                    });
+            if spawned.is_ok() {
+                workers += 1;
+            }
+        }
+        drop(done_tx);
+
+        if workers == 0 {
+            let mut buf = Vec::new();
+            for zinput_res in zinputs.by_ref() {
+                match zinput_res {
+                    Ok(input) => wtr.write_all(&convert(&input, &mut buf, options)?)?,
+                    Err(e) => options.report_unrecoverable(e, None)?,
+                }
+            }
+            return Ok(());
+        }
+
+        // Converted zips waiting for the ones before them; `None` for failed stdin lines.
+        // An abort is returned in its turn, after the records of the zips before it.
+        let mut ready: BTreeMap<usize, Option<Result<Vec<u8>, io::Error>>> = BTreeMap::new();
+        let mut next_ix: usize = 0;
+        let mut queued: usize = 0;
+        let mut in_flight: usize = 0;
+        loop {
+            // Keep every worker busy with one more zip queued behind it.
+            while in_flight < workers * 2 {
+                match zinputs.next() {
+                    Some(Ok(input)) => {
+                        job_tx.send((queued, input)).map_err(io::Error::other)?;
+                        in_flight += 1;
+                    }
+                    Some(Err(e)) => {
+                        let reported = options.report_unrecoverable(e, None);
+                        ready.insert(queued, reported.err().map(Err));
+                    }
+                    None => break,
+                }
+                queued += 1;
+            }
+            while let Some(records) = ready.remove(&next_ix) {
+                if let Some(records) = records {
+                    wtr.write_all(&records?)?;
+                }
+                next_ix += 1;
+            }
+            if in_flight == 0 {
+                return Ok(());
+            }
+            let (ix, records) = done_rx.recv().map_err(io::Error::other)?;
+            in_flight -= 1;
+            ready.insert(ix, Some(records));
+        }
+    })
+}
@@ -0,0 +1,177 @@
+// Hive-style partitioned output: `<root>/<field>=<value>/.../part-NNN.jsonl`.
+
+use crate::Blob;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Partition value of the records lacking the field (the Hive convention).
+pub const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Field holding the `YYYY-MM-DD` part of `last_modified`.
+pub const LAST_MODIFIED_DATE_FIELD: &str = "last_modified_date";
+
+/// Default cap on the partition files open at once.
+pub const DEFAULT_MAX_OPEN_PARTITIONS: usize = 64;
+
+/// Routes blob records into one JSON lines file per partition.
+///
+/// A partition field is looked up as `last_modified_date`, `content_type`,
+/// `ZipName` or a metadata key (e.g. a `--path-pattern` capture).
+/// At most `max_open` files stay open, the least recently written one being closed
+/// (and later reopened to append) to make room for another.
+pub struct PartitionedOutput {
+    root: PathBuf,
+    fields: Vec<String>,
+    max_open: usize,
+    files: Mutex<PartitionFiles>,
+}
+
+// The part files of the partitions written so far, the open ones with their last use.
+#[derive(Default)]
+struct PartitionFiles {
+    parts: HashMap<PathBuf, PathBuf>,
+    open: HashMap<PathBuf, (BufWriter<File>, u64)>,
+    uses: u64,
+}
+
+impl PartitionFiles {
+    // The open file of the partition `dir`, closing the least recently used beyond `max_open`.
+    fn get(&mut self, dir: PathBuf, max_open: usize) -> Result<&mut BufWriter<File>, io::Error> {
+        self.uses += 1;
+        let uses = self.uses;
+        if !self.open.contains_key(&dir) {
+            if self.open.len() >= max_open.max(1) {
+                let oldest = self
+                    .open
+                    .iter()
+                    .min_by_key(|(_, (_, used))| *used)
+                    .map(|(dir, _)| dir.clone());
+                if let Some((mut wtr, _)) = oldest.and_then(|dir| self.open.remove(&dir)) {
+                    wtr.flush()?;
+                }
+            }
+            let file = match self.parts.get(&dir) {
+                Some(part) => OpenOptions::new().append(true).open(part)?,
+                None => {
+                    fs::create_dir_all(&dir)?;
+                    let part = next_part_path(&dir);
+                    let file = OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(&part)?;
+                    self.parts.insert(dir.clone(), part);
+                    file
+                }
+            };
+            self.open.insert(dir.clone(), (BufWriter::new(file), uses));
+        }
+        match self.open.get_mut(&dir) {
+            Some((wtr, used)) => {
+                *used = uses;
+                Ok(wtr)
+            }
+            None => Err(io::Error::other("partition file not open")),
+        }
+    }
+}
+
+fn field_value<'a>(blob: &'a Blob, field: &str) -> Option<&'a str> {
+    match field {
+        LAST_MODIFIED_DATE_FIELD => blob.last_modified.get(..10),
+        "content_type" => Some(&blob.content_type),
+        "ZipName" => Some(&blob.metadata.zip_name),
+        key => blob.metadata.extra.get(key).map(String::as_str),
+    }
+}
+
+// Percent-encodes the characters Hive escapes in partition path components.
+fn escape_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\u{0}'..='\u{1f}'
+            | '"'
+            | '#'
+            | '%'
+            | '\''
+            | '*'
+            | '/'
+            | ':'
+            | '='
+            | '?'
+            | '\\'
+            | '\u{7f}'
+            | '{'
+            | '['
+            | ']'
+            | '^' => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// First `part-NNN.jsonl` not left by a previous run.
+fn next_part_path(dir: &Path) -> PathBuf {
+    (0..)
+        .map(|n: u32| dir.join(format!("part-{:03}.jsonl", n)))
+        .find(|path| !path.exists())
+        .unwrap_or_else(|| dir.join("part-000.jsonl"))
+}
+
+impl PartitionedOutput {
+    pub fn new(root: &Path, fields: Vec<String>) -> Self {
+        PartitionedOutput {
+            root: root.to_path_buf(),
+            fields,
+            max_open: DEFAULT_MAX_OPEN_PARTITIONS,
+            files: Mutex::default(),
+        }
+    }
+
+    /// Keeps at most `max_open` partition files open (at least one).
+    pub fn with_max_open(mut self, max_open: usize) -> Self {
+        self.max_open = max_open;
+        self
+    }
+
+    /// Directory of the partition of `blob`.
+    pub fn partition_dir(&self, blob: &Blob) -> PathBuf {
+        let mut dir = self.root.clone();
+        for field in &self.fields {
+            let value = match field_value(blob, field) {
+                Some(value) if !value.is_empty() => escape_value(value),
+                _ => DEFAULT_PARTITION.to_string(),
+            };
+            dir.push(format!("{}={}", escape_value(field), value));
+        }
+        dir
+    }
+
+    /// Calls `write` with the file of the partition of `blob`, creating it on first use.
+    pub fn write_with<F>(&self, blob: &Blob, write: F) -> Result<(), io::Error>
+    where
+        F: FnOnce(&mut BufWriter<File>) -> Result<(), io::Error>,
+    {
+        let dir = self.partition_dir(blob);
+        let mut files = self
+            .files
+            .lock()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        write(files.get(dir, self.max_open)?)
+    }
+
+    pub fn flush(&self) -> Result<(), io::Error> {
+        let mut files = self
+            .files
+            .lock()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        for (wtr, _) in files.open.values_mut() {
+            wtr.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+// Path patterns such as `/data/{tenant}/{date}/{*}.zip` whose captures become metadata.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    /// A named (`{name}`) or anonymous (`{*}`) capture of one or more non-`/` characters.
+    Capture(Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    UnclosedBrace,
+    UnmatchedBrace,
+    InvalidName(String),
+    DuplicateName(String),
+    /// Two captures without a literal in between cannot be told apart.
+    AdjacentCaptures,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::UnclosedBrace => write!(f, "unclosed '{{'"),
+            PatternError::UnmatchedBrace => write!(f, "unmatched '}}' (use '}}}}' for a literal)"),
+            PatternError::InvalidName(name) => write!(f, "invalid capture name: {:?}", name),
+            PatternError::DuplicateName(name) => write!(f, "duplicate capture name: {}", name),
+            PatternError::AdjacentCaptures => write!(f, "captures must be separated by a literal"),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// A pattern matched against the whole path of an input zip.
+///
+/// `{name}` captures one or more characters other than `/`, `{*}` does the same
+/// without keeping them, and `{{`/`}}` are literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    tokens: Vec<Token>,
+}
+
+fn is_valid_name(name: &str) -> bool {
+    // ZipName is the key of the metadata field naming the zip itself.
+    !name.is_empty()
+        && name != "ZipName"
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl FromStr for PathPattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(PatternError::UnmatchedBrace),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(PatternError::UnclosedBrace),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    } else if matches!(tokens.last(), Some(Token::Capture(_))) {
+                        return Err(PatternError::AdjacentCaptures);
+                    }
+                    let capture = match name.as_str() {
+                        "*" => None,
+                        _ if !is_valid_name(&name) => return Err(PatternError::InvalidName(name)),
+                        _ if tokens.contains(&Token::Capture(Some(name.clone()))) => {
+                            return Err(PatternError::DuplicateName(name));
+                        }
+                        _ => Some(name),
+                    };
+                    tokens.push(Token::Capture(capture));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+        Ok(PathPattern { tokens })
+    }
+}
+
+impl PathPattern {
+    /// Returns the named captures, or `None` when `path` does not match the pattern.
+    pub fn captures(&self, path: &str) -> Option<BTreeMap<String, String>> {
+        let mut captured = BTreeMap::new();
+        match_tokens(&self.tokens, path, &mut captured).then_some(captured)
+    }
+}
+
+// Backtracking match; captures take as many characters as the rest allows.
+fn match_tokens(tokens: &[Token], path: &str, captured: &mut BTreeMap<String, String>) -> bool {
+    match tokens.split_first() {
+        None => path.is_empty(),
+        Some((Token::Literal(literal), rest)) => path
+            .strip_prefix(literal.as_str())
+            .is_some_and(|remaining| match_tokens(rest, remaining, captured)),
+        Some((Token::Capture(name), rest)) => {
+            let segment_end = path.find('/').unwrap_or(path.len());
+            let ends = path[..segment_end]
+                .char_indices()
+                .map(|(ix, c)| ix + c.len_utf8())
+                .rev();
+            for end in ends {
+                if match_tokens(rest, &path[end..], captured) {
+                    if let Some(name) = name {
+                        captured.insert(name.clone(), path[..end].to_string());
+                    }
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captures(pattern: &str, path: &str) -> Result<Option<Vec<(String, String)>>, PatternError> {
+        let pattern: PathPattern = pattern.parse()?;
+        Ok(pattern
+            .captures(path)
+            .map(|captured| captured.into_iter().collect()))
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Option<Vec<(String, String)>> {
+        Some(
+            pairs
+                .iter()
+                .map(|&(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn captures_take_one_segment_each() -> Result<(), PatternError> {
+        assert_eq!(
+            captures(
+                "/data/{tenant}/{date}/{*}.zip",
+                "/data/acme/2024-01-02/a.zip"
+            )?,
+            pairs(&[("date", "2024-01-02"), ("tenant", "acme")])
+        );
+        assert_eq!(
+            captures("/data/{tenant}/{*}.zip", "/data/acme/x/a.zip")?,
+            None
+        );
+        // At least one character.
+        assert_eq!(captures("/data/{tenant}/a.zip", "/data//a.zip")?, None);
+        assert_eq!(captures("{name}.zip", "a.zip.bak")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn captures_are_greedy_and_backtrack() -> Result<(), PatternError> {
+        assert_eq!(
+            captures("{name}.zip", "a.b.zip")?,
+            pairs(&[("name", "a.b")])
+        );
+        assert_eq!(
+            captures("{base}-{part}.zip", "a-b-c.zip")?,
+            pairs(&[("base", "a-b"), ("part", "c")])
+        );
+        // The first capture gives back what the literal after it needs.
+        assert_eq!(
+            captures("{a}.{b}.gz", "x.y.z.gz")?,
+            pairs(&[("a", "x.y"), ("b", "z")])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() -> Result<(), PatternError> {
+        assert_eq!(
+            captures("{{{name}}}.zip", "{a}.zip")?,
+            pairs(&[("name", "a")])
+        );
+        assert_eq!(captures("{{x}}.zip", "{x}.zip")?, pairs(&[]));
+        assert_eq!(captures("{{x}}.zip", "x.zip")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        let parse = |pattern: &str| pattern.parse::<PathPattern>().err();
+        assert_eq!(parse("{a}{b}"), Some(PatternError::AdjacentCaptures));
+        assert_eq!(parse("{a}{*}.zip"), Some(PatternError::AdjacentCaptures));
+        assert_eq!(
+            parse("{a}/{a}.zip"),
+            Some(PatternError::DuplicateName("a".into()))
+        );
+        assert_eq!(
+            parse("/{ZipName}.zip"),
+            Some(PatternError::InvalidName("ZipName".into()))
+        );
+        assert_eq!(parse("{}"), Some(PatternError::InvalidName("".into())));
+        assert_eq!(
+            parse("{a-b}"),
+            Some(PatternError::InvalidName("a-b".into()))
+        );
+        assert_eq!(parse("{a"), Some(PatternError::UnclosedBrace));
+        assert_eq!(parse("a}"), Some(PatternError::UnmatchedBrace));
+        // Anonymous captures may repeat.
+        assert_eq!(parse("{*}/{*}.zip"), None);
+    }
+}
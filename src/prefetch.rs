@@ -0,0 +1,95 @@
+// Reads the next zips on a background thread while the current one is converted.
+
+use crate::{ReadError, ZipInput, filename2buf};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+
+pub struct Loaded {
+    pub input: ZipInput,
+    pub buf: Vec<u8>,
+    pub result: Result<(), ReadError>,
+}
+
+// A queued input: either a zip being read by the worker or a failed stdin line.
+enum Slot {
+    Reading,
+    Failed(io::Error),
+}
+
+/// Calls `f` for each input, in the given order, with up to `depth` zips read ahead.
+///
+/// Falls back to reading on the calling thread when threads are unavailable
+/// (e.g. wasm32-wasip1).
+pub fn for_each_prefetched<I, F>(
+    zfilenames: I,
+    depth: usize,
+    limit: u64,
+    mut f: F,
+) -> Result<(), io::Error>
+where
+    I: Iterator<Item = Result<ZipInput, io::Error>>,
+    F: FnMut(Result<&Loaded, io::Error>) -> Result<(), io::Error>,
+{
+    let mut zfilenames = zfilenames.fuse();
+    thread::scope(|scope| {
+        let (req_tx, req_rx) = mpsc::channel::<(ZipInput, Vec<u8>)>();
+        let (res_tx, res_rx) = mpsc::channel::<Loaded>();
+        let spawned =
+            thread::Builder::new()
+                .name("prefetch".into())
+                .spawn_scoped(scope, move || {
+                    for (input, mut buf) in req_rx {
+                        let result = filename2buf(&input.path, &mut buf, limit);
+                        if res_tx.send(Loaded { input, buf, result }).is_err() {
+                            break;
+                        }
+                    }
+                });
+        if spawned.is_err() {
+            let mut buf = Vec::new();
+            for zfilename_res in zfilenames.by_ref() {
+                match zfilename_res {
+                    Ok(input) => {
+                        let result = filename2buf(&input.path, &mut buf, limit);
+                        let loaded = Loaded { input, buf, result };
+                        f(Ok(&loaded))?;
+                        buf = loaded.buf;
+                    }
+                    Err(e) => f(Err(e))?,
+                }
+            }
+            return Ok(());
+        }
+
+        // One buffer is being converted while `depth` others are being filled.
+        let mut pool: Vec<Vec<u8>> = (0..=depth).map(|_| Vec::new()).collect();
+        let mut pending: VecDeque<Slot> = VecDeque::new();
+        let mut reading: usize = 0;
+        loop {
+            while reading < depth {
+                match zfilenames.next() {
+                    Some(Ok(input)) => {
+                        let buf = pool.pop().unwrap_or_default();
+                        req_tx.send((input, buf)).map_err(io::Error::other)?;
+                        pending.push_back(Slot::Reading);
+                        reading += 1;
+                    }
+                    Some(Err(e)) => pending.push_back(Slot::Failed(e)),
+                    None => break,
+                }
+            }
+            match pending.pop_front() {
+                None => return Ok(()),
+                Some(Slot::Failed(e)) => f(Err(e))?,
+                Some(Slot::Reading) => {
+                    let loaded = res_rx.recv().map_err(io::Error::other)?;
+                    reading -= 1;
+                    f(Ok(&loaded))?;
+                    pool.push(loaded.buf);
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,129 @@
+// Progress of a conversion run, reported after each zip.
+
+use crate::{LogFormat, RunStats};
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Counts of a run so far, with the rates since it started.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename = "progress")]
+pub struct Progress {
+    pub zips_done: u64,
+    pub entries_emitted: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub elapsed_secs: f64,
+    pub zips_per_sec: f64,
+    pub bytes_in_per_sec: f64,
+}
+
+impl From<&RunStats> for Progress {
+    fn from(stats: &RunStats) -> Self {
+        let rate = |count: u64| {
+            if stats.wall_time_secs > 0.0 {
+                count as f64 / stats.wall_time_secs
+            } else {
+                0.0
+            }
+        };
+        let zips_done = stats.zips_done();
+        Progress {
+            zips_done,
+            entries_emitted: stats.entries_emitted,
+            bytes_in: stats.bytes_in,
+            bytes_out: stats.bytes_out,
+            elapsed_secs: stats.wall_time_secs,
+            zips_per_sec: rate(zips_done),
+            bytes_in_per_sec: rate(stats.bytes_in),
+        }
+    }
+}
+
+/// Tab-separated `key:value` fields, e.g. `status:progress\tzips_done:12\t...`.
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "status:progress\tzips_done:{}\tentries_emitted:{}\tbytes_in:{}\tbytes_out:{}\telapsed_secs:{:.1}\tzips_per_sec:{:.1}\tbytes_in_per_sec:{:.0}",
+            self.zips_done,
+            self.entries_emitted,
+            self.bytes_in,
+            self.bytes_out,
+            self.elapsed_secs,
+            self.zips_per_sec,
+            self.bytes_in_per_sec
+        )
+    }
+}
+
+/// Receives the progress of a conversion after each zip; shared by the worker threads.
+pub trait ProgressSink: Sync {
+    fn progress(&self, progress: &Progress);
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(&Progress) + Sync,
+{
+    fn progress(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// Writes a progress line to a writer, e.g. stderr, at most once per `interval`.
+pub struct ProgressLog {
+    format: LogFormat,
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl ProgressLog {
+    pub fn new(format: LogFormat, interval: Duration, out: Box<dyn Write + Send>) -> Self {
+        ProgressLog {
+            format,
+            interval,
+            last: Mutex::new(None),
+            out: Mutex::new(out),
+        }
+    }
+
+    /// Writes a line whatever the interval, e.g. for the end of the run.
+    pub fn write_line(&self, progress: &Progress) -> Result<(), io::Error> {
+        let mut out = self
+            .out
+            .lock()
+            .map_err(|_| io::Error::other("progress writer poisoned"))?;
+        match self.format {
+            LogFormat::Text => writeln!(out, "{}", progress)?,
+            LogFormat::Json => {
+                serde_json::to_writer(&mut *out, progress)?;
+                writeln!(out)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+impl ProgressSink for ProgressLog {
+    fn progress(&self, progress: &Progress) {
+        let due = match self.last.lock() {
+            Ok(mut last) => {
+                let now = Instant::now();
+                let due = last.is_none_or(|at| now.duration_since(at) >= self.interval);
+                if due {
+                    *last = Some(now);
+                }
+                due
+            }
+            Err(_) => false,
+        };
+        if due {
+            // Losing a progress line must not abort the conversion.
+            let _ = self.write_line(progress);
+        }
+    }
+}
@@ -0,0 +1,454 @@
+// Serialization of records with their top-level entries rewritten on the fly (renamed,
+// dropped or spliced), without building an intermediate `serde_json::Value`.
+
+use serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
+use std::fmt::{self, Write as _};
+use std::marker::PhantomData;
+
+/// What becomes of each top-level entry of a record.
+pub(crate) trait Rewrite {
+    /// Writes the entry `key: value` of the record into `map`, as is or rewritten.
+    fn entry<M, V>(&self, map: &mut M, key: &str, value: &V) -> Result<(), M::Error>
+    where
+        M: SerializeMap,
+        V: Serialize + ?Sized;
+}
+
+/// Serializes `record`, a map or a struct, as a map of its entries rewritten by `rewrite`.
+pub(crate) fn serialize_rewritten<T, S, R>(
+    record: &T,
+    serializer: S,
+    rewrite: &R,
+) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + ?Sized,
+    S: Serializer,
+    R: Rewrite,
+{
+    let mut map = serializer.serialize_map(None)?;
+    record.serialize(Entries::new(&mut map, rewrite))?;
+    map.end()
+}
+
+/// Serializes a map or a struct as entries of the `map` being written, through a [`Rewrite`].
+pub(crate) struct Entries<'m, M, R> {
+    map: &'m mut M,
+    rewrite: &'m R,
+    // The key of the entry whose value comes next, reused from entry to entry.
+    key: String,
+}
+
+impl<'m, M, R> Entries<'m, M, R> {
+    pub(crate) fn new(map: &'m mut M, rewrite: &'m R) -> Self {
+        Entries {
+            map,
+            rewrite,
+            key: String::new(),
+        }
+    }
+}
+
+fn not_a_map<E: ser::Error>() -> E {
+    E::custom("a record must be a map or a struct")
+}
+
+impl<M, R> Serializer for Entries<'_, M, R>
+where
+    M: SerializeMap,
+    R: Rewrite,
+{
+    type Ok = ();
+    type Error = M::Error;
+    type SerializeSeq = Impossible<(), M::Error>;
+    type SerializeTuple = Impossible<(), M::Error>;
+    type SerializeTupleStruct = Impossible<(), M::Error>;
+    type SerializeTupleVariant = Impossible<(), M::Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), M::Error>;
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, M::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, M::Error> {
+        Ok(self)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    // No entries, as for a flattened `None`.
+    fn serialize_none(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_char(self, _v: char) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> Result<(), M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), M::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(not_a_map())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, M::Error> {
+        Err(not_a_map())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, M::Error> {
+        Err(not_a_map())
+    }
+}
+
+impl<M, R> SerializeMap for Entries<'_, M, R>
+where
+    M: SerializeMap,
+    R: Rewrite,
+{
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), M::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key.clear();
+        key.serialize(KeyCapture {
+            key: &mut self.key,
+            error: PhantomData,
+        })
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.rewrite.entry(self.map, &self.key, value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+impl<M, R> SerializeStruct for Entries<'_, M, R>
+where
+    M: SerializeMap,
+    R: Rewrite,
+{
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.rewrite.entry(self.map, key, value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+// Copies a string map key into `key`.
+struct KeyCapture<'k, E> {
+    key: &'k mut String,
+    error: PhantomData<E>,
+}
+
+fn not_a_string<E: ser::Error>() -> E {
+    E::custom("a record key must be a string")
+}
+
+impl<E> Serializer for KeyCapture<'_, E>
+where
+    E: ser::Error,
+{
+    type Ok = ();
+    type Error = E;
+    type SerializeSeq = Impossible<(), E>;
+    type SerializeTuple = Impossible<(), E>;
+    type SerializeTupleStruct = Impossible<(), E>;
+    type SerializeTupleVariant = Impossible<(), E>;
+    type SerializeMap = Impossible<(), E>;
+    type SerializeStruct = Impossible<(), E>;
+    type SerializeStructVariant = Impossible<(), E>;
+
+    fn serialize_str(self, v: &str) -> Result<(), E> {
+        self.key.push_str(v);
+        Ok(())
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<(), E>
+    where
+        T: fmt::Display + ?Sized,
+    {
+        write!(self.key, "{}", value).map_err(E::custom)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<(), E>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_char(self, _v: char) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_none(self) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<(), E>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(not_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> Result<(), E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), E>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(not_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, E> {
+        Err(not_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, E> {
+        Err(not_a_string())
+    }
+}
@@ -0,0 +1,100 @@
+// Output sharded into numbered files: `<dir>/blobs-NNNNN.jsonl`.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes newline-terminated records into `blobs-00001.jsonl`, `blobs-00002.jsonl`, ...
+/// in a directory, moving to the next file once one holds `max_records` records or
+/// `max_bytes` bytes.
+///
+/// Files are only switched between records, so a file may exceed `max_bytes` by
+/// the last record written to it. Numbers of files left by a previous run are skipped.
+pub struct RotatingWriter {
+    dir: PathBuf,
+    max_records: Option<u64>,
+    max_bytes: Option<u64>,
+    next_index: u32,
+    current: Option<BufWriter<File>>,
+    records: u64,
+    bytes: u64,
+}
+
+impl RotatingWriter {
+    /// The directory is created on the first write; `None` limits never rotate.
+    pub fn new(dir: &Path, max_records: Option<u64>, max_bytes: Option<u64>) -> Self {
+        RotatingWriter {
+            dir: dir.to_path_buf(),
+            max_records,
+            max_bytes,
+            next_index: 1,
+            current: None,
+            records: 0,
+            bytes: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.max_records.is_some_and(|max| self.records >= max)
+            || self.max_bytes.is_some_and(|max| self.bytes >= max)
+    }
+
+    fn open_next(&mut self) -> Result<BufWriter<File>, io::Error> {
+        fs::create_dir_all(&self.dir)?;
+        loop {
+            let path = self.dir.join(format!("blobs-{:05}.jsonl", self.next_index));
+            self.next_index += 1;
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok(BufWriter::new(file)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Closes the current file; the next write opens a new one.
+    fn rotate(&mut self) -> Result<(), io::Error> {
+        if let Some(mut current) = self.current.take() {
+            current.flush()?;
+        }
+        self.records = 0;
+        self.bytes = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // No more than the rest of the current record, so that rotating never splits one.
+        let end = buf
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(buf.len(), |i| i + 1);
+        let current = match &mut self.current {
+            Some(current) => current,
+            None => {
+                let next = self.open_next()?;
+                self.current.insert(next)
+            }
+        };
+        let n = current.write(&buf[..end])?;
+        self.bytes += n as u64;
+        if buf[..n].last() == Some(&b'\n') {
+            self.records += 1;
+            if self.is_full() {
+                self.rotate()?;
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(current) => current.flush(),
+            None => Ok(()),
+        }
+    }
+}
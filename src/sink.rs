@@ -0,0 +1,18 @@
+// Destinations receiving the blob records one at a time instead of the output stream.
+
+use crate::Blob;
+use std::io;
+
+/// Receives each blob with its serialized record (without the newline); shared by the worker threads.
+pub trait BlobSink: Sync {
+    fn send(&self, blob: &Blob, record: &[u8]) -> Result<(), io::Error>;
+}
+
+impl<F> BlobSink for F
+where
+    F: Fn(&Blob, &[u8]) -> Result<(), io::Error> + Sync,
+{
+    fn send(&self, blob: &Blob, record: &[u8]) -> Result<(), io::Error> {
+        self(blob, record)
+    }
+}
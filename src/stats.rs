@@ -0,0 +1,151 @@
+// Counts of a conversion run, for the summary report.
+
+use crate::Diagnostic;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct RunStats {
+    /// Zips whose records were written.
+    pub zips_processed: u64,
+    /// Zips not (completely) converted, by reason.
+    pub zips_skipped: BTreeMap<String, u64>,
+    /// Zips not converted again as the checkpoint has them unchanged.
+    pub zips_unchanged: u64,
+    /// Zips processed whose remaining entries were skipped for `max_entries_per_zip`
+    /// or the output limit.
+    pub zips_truncated: u64,
+    pub entries_emitted: u64,
+    /// Entries not converted for their size, compression method or data.
+    pub entries_skipped: u64,
+    /// Blobs dropped by `Options::transform`.
+    pub blobs_dropped: u64,
+    /// Bytes of the zips read.
+    pub bytes_in: u64,
+    /// Bytes of the records written (partition files included).
+    pub bytes_out: u64,
+    pub wall_time_secs: f64,
+}
+
+impl RunStats {
+    /// Zips processed or skipped.
+    pub fn zips_done(&self) -> u64 {
+        self.zips_processed + self.zips_skipped.values().sum::<u64>()
+    }
+
+    fn add(&mut self, other: &RunStats) {
+        self.zips_processed += other.zips_processed;
+        for (reason, count) in &other.zips_skipped {
+            *self.zips_skipped.entry(reason.clone()).or_default() += count;
+        }
+        self.zips_unchanged += other.zips_unchanged;
+        self.zips_truncated += other.zips_truncated;
+        self.entries_emitted += other.entries_emitted;
+        self.entries_skipped += other.entries_skipped;
+        self.blobs_dropped += other.blobs_dropped;
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+        self.wall_time_secs += other.wall_time_secs;
+    }
+
+    // Counts the skip a diagnostic reports, if any.
+    fn count_diagnostic(&mut self, diagnostic: &Diagnostic) {
+        match diagnostic.status.as_str() {
+            "zip_skipped" => {
+                let reason = diagnostic.reason.as_deref().unwrap_or("unknown");
+                *self.zips_skipped.entry(reason.into()).or_default() += 1;
+            }
+            "zip_processing_failed" | "unrecoverable_error" => {
+                *self
+                    .zips_skipped
+                    .entry(diagnostic.status.clone())
+                    .or_default() += 1;
+            }
+            "item_skipped" => self.entries_skipped += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Collects the [`RunStats`] of a conversion; shared by the worker threads.
+#[derive(Debug)]
+pub struct StatsRecorder {
+    stats: Mutex<RunStats>,
+    started: Instant,
+}
+
+impl Default for StatsRecorder {
+    fn default() -> Self {
+        StatsRecorder {
+            stats: Mutex::default(),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl StatsRecorder {
+    pub fn update<F>(&self, f: F)
+    where
+        F: FnOnce(&mut RunStats),
+    {
+        if let Ok(mut stats) = self.stats.lock() {
+            f(&mut stats)
+        }
+    }
+
+    pub fn count_diagnostic(&self, diagnostic: &Diagnostic) {
+        self.update(|stats| stats.count_diagnostic(diagnostic))
+    }
+
+    /// Adds the counts of another run, e.g. one returned by `zfilenames2zip2blobs2jsons2writer`.
+    pub fn add(&self, other: &RunStats) {
+        self.update(|stats| stats.add(other))
+    }
+
+    /// The counts so far, with the time since the recorder was created as the wall time.
+    pub fn snapshot(&self) -> RunStats {
+        self.finish(self.started.elapsed())
+    }
+
+    /// The counts so far, with `elapsed` as the wall time.
+    pub fn finish(&self, elapsed: Duration) -> RunStats {
+        let mut stats = self
+            .stats
+            .lock()
+            .map(|stats| stats.clone())
+            .unwrap_or_default();
+        stats.wall_time_secs = elapsed.as_secs_f64();
+        stats
+    }
+}
+
+// Passes the writes through, counting the bytes written.
+pub struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        CountingWriter { inner, count: 0 }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,156 @@
+// Tar archives held in memory, converted like zips.
+
+use crate::{ArchiveEntry, ArchiveReader, CentralRecord, EntryMetadata};
+use chrono::DateTime;
+use std::io;
+use tar::EntryType;
+
+// File type bits of `EntryMetadata::mode`, which tar headers usually leave out.
+const MODE_FILE: u32 = 0o100000;
+const MODE_DIR: u32 = 0o040000;
+
+/// The regular files and directories of a tar held in memory.
+///
+/// Other entries (links, devices, ...) are left out.
+pub struct TarEntries<'a> {
+    entries: Vec<TarEntry<'a>>,
+}
+
+/// One entry of a [`TarEntries`]: its metadata and its data in the tar.
+#[derive(Clone)]
+pub struct TarEntry<'a> {
+    meta: EntryMetadata,
+    offset: u64,
+    data: &'a [u8],
+}
+
+impl<'a> TarEntries<'a> {
+    /// Reads the headers of the tar in `tardata` (GNU long names and pax paths included).
+    pub fn from_slice(tardata: &'a [u8]) -> Result<Self, io::Error> {
+        let mut archive = tar::Archive::new(tardata);
+        let mut entries = Vec::new();
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let header = entry.header();
+            let (is_dir, file_type) = match header.entry_type() {
+                EntryType::Regular | EntryType::Continuous => (false, MODE_FILE),
+                EntryType::Directory => (true, MODE_DIR),
+                _ => continue,
+            };
+            let mut name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            if is_dir && !name.ends_with('/') {
+                name.push('/');
+            }
+            let size = entry.size();
+            let offset = entry.raw_file_position();
+            let data = usize::try_from(offset)
+                .ok()
+                .zip(usize::try_from(size).ok())
+                .and_then(|(start, len)| tardata.get(start..start.checked_add(len)?))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "tar entry past the end of the tar",
+                    )
+                })?;
+            let mtime = header.mtime().unwrap_or_default();
+            entries.push(TarEntry {
+                meta: EntryMetadata {
+                    name,
+                    raw_name: None,
+                    last_modified: i64::try_from(mtime)
+                        .ok()
+                        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                        .unwrap_or_default(),
+                    dos_time: false,
+                    compressed_size: size,
+                    uncompressed_size: size,
+                    compression_method: 0,
+                    crc32: 0,
+                    is_dir,
+                    mode: file_type | (header.mode().unwrap_or_default() & 0o7777),
+                },
+                offset,
+                data,
+            });
+        }
+        Ok(TarEntries { entries })
+    }
+}
+
+impl ArchiveReader for TarEntries<'_> {
+    fn entries(
+        &self,
+    ) -> Box<dyn Iterator<Item = Result<Box<dyn ArchiveEntry + '_>, io::Error>> + '_> {
+        Box::new(
+            self.entries
+                .iter()
+                .map(|entry| Ok(Box::new(entry.clone()) as Box<dyn ArchiveEntry>)),
+        )
+    }
+
+    fn comment(&self) -> Result<String, io::Error> {
+        Ok(String::new())
+    }
+}
+
+// Tar entries are neither compressed nor encrypted: the password is not used.
+impl ArchiveEntry for TarEntry<'_> {
+    fn name(&self) -> &str {
+        &self.meta.name
+    }
+
+    fn metadata(&self) -> EntryMetadata {
+        self.meta.clone()
+    }
+
+    fn has_crc32(&self) -> bool {
+        false
+    }
+
+    // The mode in the high half of the external attributes, as zips made on Unix have it.
+    fn central_record(&self) -> Result<CentralRecord, io::Error> {
+        Ok(CentralRecord {
+            flags: 0,
+            external_attributes: self.meta.mode << 16,
+            comment: String::new(),
+        })
+    }
+
+    fn data_offset(&self) -> Result<u64, io::Error> {
+        Ok(self.offset)
+    }
+
+    fn read_body_with_password(
+        &self,
+        buf: &mut Vec<u8>,
+        _: Option<&[u8]>,
+    ) -> Result<(), io::Error> {
+        buf.clear();
+        buf.extend_from_slice(self.data);
+        Ok(())
+    }
+
+    fn read_body_prefix(
+        &self,
+        buf: &mut Vec<u8>,
+        len: u64,
+        _: Option<&[u8]>,
+    ) -> Result<(), io::Error> {
+        let len = usize::try_from(len)
+            .unwrap_or(usize::MAX)
+            .min(self.data.len());
+        buf.clear();
+        buf.extend_from_slice(&self.data[..len]);
+        Ok(())
+    }
+
+    fn read_body_chunks(
+        &self,
+        chunk_len: usize,
+        _: Option<&[u8]>,
+        on_chunk: &mut dyn FnMut(&[u8]) -> Result<(), io::Error>,
+    ) -> Result<(), io::Error> {
+        self.data.chunks(chunk_len.max(1)).try_for_each(on_chunk)
+    }
+}
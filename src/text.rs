@@ -0,0 +1,40 @@
+// Statistics for entries handled as text.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStats {
+    pub line_count: u64,
+    pub byte_count: u64,
+    /// Longest line in bytes, excluding the line terminator.
+    pub max_line_length: u64,
+}
+
+/// Entries are handled as text when they are valid UTF-8 without NUL bytes.
+pub fn is_text(data: &[u8]) -> bool {
+    !data.contains(&0) && std::str::from_utf8(data).is_ok()
+}
+
+pub fn text_stats(data: &[u8]) -> TextStats {
+    let mut line_count: u64 = 0;
+    let mut max_line_length: u64 = 0;
+    let mut current: u64 = 0;
+    for &b in data {
+        if b == b'\n' {
+            line_count += 1;
+            max_line_length = max_line_length.max(current);
+            current = 0;
+        } else if b != b'\r' {
+            current += 1;
+        }
+    }
+    if current > 0 {
+        line_count += 1;
+        max_line_length = max_line_length.max(current);
+    }
+    TextStats {
+        line_count,
+        byte_count: data.len() as u64,
+        max_line_length,
+    }
+}
@@ -0,0 +1,20 @@
+// A hook changing the blobs before they are written, e.g. to add or redact fields.
+
+use crate::Blob;
+
+/// Changes, replaces or drops each blob before it is written; shared by the worker threads.
+pub trait BlobTransform: Sync {
+    /// The blob to write in place of `blob`, or `None` to drop it.
+    fn transform<'a>(&self, blob: Blob<'a>) -> Option<Blob<'a>>;
+}
+
+/// Implemented by the functions (`fn(Blob<'_>) -> Option<Blob<'_>>`); a closure needs its
+/// argument and result types spelled out to be one.
+impl<F> BlobTransform for F
+where
+    F: for<'a> Fn(Blob<'a>) -> Option<Blob<'a>> + Sync,
+{
+    fn transform<'a>(&self, blob: Blob<'a>) -> Option<Blob<'a>> {
+        self(blob)
+    }
+}
@@ -0,0 +1,80 @@
+// Hierarchical (one document per archive) output.
+
+use crate::Blob;
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct ArchiveTree {
+    pub zip_name: String,
+    pub root: TreeNode,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TreeNode {
+    Directory {
+        name: String,
+        children: Vec<TreeNode>,
+    },
+    File {
+        name: String,
+        blob: Box<Blob<'static>>,
+    },
+}
+
+#[derive(Default)]
+pub struct TreeBuilder {
+    name: String,
+    dirs: Vec<TreeBuilder>,
+    files: Vec<(String, Blob<'static>)>,
+}
+
+impl TreeBuilder {
+    fn dir(&mut self, name: &str) -> &mut TreeBuilder {
+        let ix = match self.dirs.iter().position(|d| d.name == name) {
+            Some(ix) => ix,
+            None => {
+                self.dirs.push(TreeBuilder {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                self.dirs.len() - 1
+            }
+        };
+        &mut self.dirs[ix]
+    }
+
+    /// Places the blob by its `/`-separated name; directory entries only create the directory.
+    pub fn insert(&mut self, blob: Blob<'static>) {
+        let path = blob.name.clone();
+        let is_dir = path.ends_with('/');
+        let mut components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        let file_name = if is_dir { None } else { components.pop() };
+        let mut node = self;
+        for component in components {
+            node = node.dir(component);
+        }
+        if let Some(file_name) = file_name {
+            node.files.push((file_name.to_string(), blob));
+        }
+    }
+
+    fn into_node(self) -> TreeNode {
+        let mut children: Vec<TreeNode> = self.dirs.into_iter().map(Self::into_node).collect();
+        children.extend(self.files.into_iter().map(|(name, blob)| TreeNode::File {
+            name,
+            blob: Box::new(blob),
+        }));
+        TreeNode::Directory {
+            name: self.name,
+            children,
+        }
+    }
+
+    pub fn build(self, zip_name: &str) -> ArchiveTree {
+        ArchiveTree {
+            zip_name: zip_name.to_string(),
+            root: self.into_node(),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// Validation of blob records produced by this crate.
+
+use crate::{Blob, encoding, external};
+use chrono::DateTime;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, BufRead, BufWriter, Write};
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "violation", rename_all = "snake_case")]
+pub enum Violation {
+    /// The line could not be parsed as a blob record.
+    InvalidRecord {
+        reason: String,
+    },
+    UnsupportedTransferEncoding {
+        encoding: String,
+    },
+    InvalidBody {
+        reason: String,
+    },
+    ContentLengthMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    InvalidTimestamp {
+        value: String,
+        reason: String,
+    },
+    UnreadableBody {
+        uri: String,
+        reason: String,
+    },
+    Sha256Mismatch {
+        expected: String,
+        actual: String,
+    },
+    Crc32Mismatch {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::InvalidRecord { reason } => write!(f, "invalid record: {}", reason),
+            Violation::UnsupportedTransferEncoding { encoding } => {
+                write!(f, "unsupported content_transfer_encoding: {}", encoding)
+            }
+            Violation::InvalidBody { reason } => write!(f, "body does not decode: {}", reason),
+            Violation::ContentLengthMismatch { expected, actual } => write!(
+                f,
+                "content_length mismatch: expected {}, decoded {}",
+                expected, actual
+            ),
+            Violation::InvalidTimestamp { value, reason } => {
+                write!(f, "invalid last_modified {}: {}", value, reason)
+            }
+            Violation::UnreadableBody { uri, reason } => {
+                write!(f, "body_uri {} is unreadable: {}", uri, reason)
+            }
+            Violation::Sha256Mismatch { expected, actual } => {
+                write!(
+                    f,
+                    "sha256 mismatch: expected {}, actual {}",
+                    expected, actual
+                )
+            }
+            Violation::Crc32Mismatch { expected, actual } => {
+                write!(
+                    f,
+                    "crc32 mismatch: expected {}, actual {}",
+                    expected, actual
+                )
+            }
+        }
+    }
+}
+
+fn decode_body(content_transfer_encoding: &str, body: &str) -> Result<Vec<u8>, Violation> {
+    match encoding::decode(content_transfer_encoding, body) {
+        Some(decoded) => decoded.map_err(|reason| Violation::InvalidBody { reason }),
+        None => Err(Violation::UnsupportedTransferEncoding {
+            encoding: content_transfer_encoding.to_string(),
+        }),
+    }
+}
+
+/// Checks that a blob is internally consistent.
+///
+/// The body, inline or at `body_uri`, is checked against `content_length`, `crc32` and
+/// `sha256`; records without either (metadata only) get the `last_modified` check alone.
+/// An empty result means the blob is valid.
+pub fn validate_blob(blob: &Blob) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    // Records without a body (`BodyMode::MetadataOnly`) have nothing to check it against.
+    let decoded = match (&blob.body, &blob.body_uri) {
+        (Some(body), _) => Some(decode_body(&blob.content_transfer_encoding, body)),
+        (None, Some(uri)) => Some(external::load(uri).map_err(|e| Violation::UnreadableBody {
+            uri: uri.clone(),
+            reason: e.to_string(),
+        })),
+        (None, None) => None,
+    };
+
+    match decoded {
+        None => {}
+        Some(Ok(decoded)) => {
+            if decoded.len() as u64 != blob.content_length {
+                violations.push(Violation::ContentLengthMismatch {
+                    expected: blob.content_length,
+                    actual: decoded.len() as u64,
+                });
+            }
+            // The CRC-32 of a truncated or chunked entry is that of the whole entry.
+            let partial = blob.truncated == Some(true) || blob.part.is_some();
+            if let Some(expected) = blob.crc32.as_ref().filter(|_| !partial) {
+                let actual = format!("{:08x}", rawzip::crc32(&decoded));
+                if !expected.eq_ignore_ascii_case(&actual) {
+                    violations.push(Violation::Crc32Mismatch {
+                        expected: expected.clone(),
+                        actual,
+                    });
+                }
+            }
+            if let Some(expected) = &blob.sha256 {
+                let actual = external::sha256_hex(&decoded);
+                if !expected.eq_ignore_ascii_case(&actual) {
+                    violations.push(Violation::Sha256Mismatch {
+                        expected: expected.clone(),
+                        actual,
+                    });
+                }
+            }
+        }
+        Some(Err(violation)) => violations.push(violation),
+    }
+
+    if let Err(e) = DateTime::parse_from_rfc3339(&blob.last_modified) {
+        violations.push(Violation::InvalidTimestamp {
+            value: blob.last_modified.clone(),
+            reason: e.to_string(),
+        });
+    }
+
+    violations
+}
+
+#[derive(Serialize, Debug)]
+pub struct VerifyReport {
+    /// 1-based line number of the record.
+    pub line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub violations: Vec<Violation>,
+}
+
+/// Validates each JSON line of `rdr` and writes a report for every invalid record.
+///
+/// Returns the number of invalid records.
+pub fn jsons2violations2writer<R, W>(rdr: R, wtr: &mut BufWriter<W>) -> Result<u64, io::Error>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut invalid: u64 = 0;
+    for (ix, line_res) in rdr.lines().enumerate() {
+        let line = line_res?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let report = match serde_json::from_str::<Blob>(&line) {
+            Ok(blob) => VerifyReport {
+                line: ix as u64 + 1,
+                violations: validate_blob(&blob),
+                name: Some(blob.name),
+            },
+            Err(e) => VerifyReport {
+                line: ix as u64 + 1,
+                name: None,
+                violations: vec![Violation::InvalidRecord {
+                    reason: e.to_string(),
+                }],
+            },
+        };
+        if report.violations.is_empty() {
+            continue;
+        }
+        invalid += 1;
+        serde_json::to_writer(&mut *wtr, &report)?;
+        writeln!(&mut *wtr)?;
+    }
+    Ok(invalid)
+}
+
+pub fn stdin2jsons2violations2stdout() -> Result<u64, io::Error> {
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    let invalid = jsons2violations2writer(io::stdin().lock(), &mut writer)?;
+    writer.flush()?;
+    Ok(invalid)
+}
@@ -0,0 +1,83 @@
+// Discovery of the zips under a directory tree.
+
+use crate::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Iterator returned by [`dir2zfilenames`].
+pub struct DirZipNames {
+    // Paths still to visit, the next one last.
+    stack: Vec<PathBuf>,
+    extensions: Vec<String>,
+}
+
+// Extensions may have several parts, e.g. `tar.gz`.
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_ascii_lowercase)
+        .is_some_and(|name| {
+            extensions
+                .iter()
+                .any(|known| name.ends_with(&format!(".{}", known.to_ascii_lowercase())))
+        })
+}
+
+impl DirZipNames {
+    // Queues the children of `dir` so that they are visited in name order.
+    fn push_children(&mut self, dir: &Path) -> Result<(), io::Error> {
+        let mut children = fs::read_dir(dir)?
+            .map(|entry_res| entry_res.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        children.sort_unstable_by(|a, b| b.cmp(a));
+        self.stack.extend(children);
+        Ok(())
+    }
+}
+
+impl Iterator for DirZipNames {
+    type Item = Result<String, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(path) = self.stack.pop() {
+            // Symlinks to directories are not followed, so that cycles cannot occur.
+            let visited = fs::symlink_metadata(&path).and_then(|meta| {
+                if meta.file_type().is_dir() {
+                    self.push_children(&path).map(|()| true)
+                } else {
+                    Ok(false)
+                }
+            });
+            match visited {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    return Some(Err(io::Error::new(
+                        e.kind(),
+                        format!("{}: {}", path.display(), e),
+                    )));
+                }
+            }
+            if !has_extension(&path, &self.extensions) || !path.is_file() {
+                continue;
+            }
+            return Some(path.into_os_string().into_string().map_err(|path| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::Encoding(format!("path {}", PathBuf::from(path).display())),
+                )
+            }));
+        }
+        None
+    }
+}
+
+/// Walks `root` recursively, yielding the files with one of `extensions`
+/// (case-insensitive, without the dot) in name order.
+pub fn dir2zfilenames(root: &Path, extensions: &[String]) -> DirZipNames {
+    DirZipNames {
+        stack: vec![root.to_path_buf()],
+        extensions: extensions.to_vec(),
+    }
+}
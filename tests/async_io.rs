@@ -0,0 +1,70 @@
+#![cfg(feature = "tokio")]
+
+use rs_rawzips2blobs2jsons::{
+    Blob, Options, ReadError, buf2zip2blobs2jsons2writer_async, rdr2buf_async,
+};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn conversion_leaves_the_worker_to_other_tasks() -> Result<(), Box<dyn Error>> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .build()?;
+    let ticked = Arc::new(AtomicBool::new(false));
+    let zipdata = fs::read("sample.d/hw0.zip")?;
+
+    let waiting = Arc::clone(&ticked);
+    let ticker = Arc::clone(&ticked);
+    let converted = runtime.block_on(async move {
+        let conversion = tokio::spawn(async move {
+            // Blocks until the other task, queued on the only worker, has run.
+            let sink = move |_: &Blob, _: &[u8]| -> Result<(), io::Error> {
+                let start = Instant::now();
+                while !waiting.load(Ordering::SeqCst) {
+                    if start.elapsed() > Duration::from_secs(5) {
+                        return Err(io::Error::other("the other task did not run"));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Ok(())
+            };
+            let options = Options {
+                sink: Some(&sink),
+                ..Options::default()
+            };
+            let mut out: Vec<u8> = Vec::new();
+            buf2zip2blobs2jsons2writer_async("hw0.zip", &zipdata, &options, &mut out)
+                .await
+                .map_err(|e| e.to_string())
+        });
+        tokio::spawn(async move { ticker.store(true, Ordering::SeqCst) });
+        conversion.await
+    })?;
+    converted?;
+    assert!(ticked.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[test]
+fn the_largest_size_limit_reads_everything() -> Result<(), Box<dyn Error>> {
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        let mut buf = Vec::new();
+        assert!(matches!(
+            rdr2buf_async(&b"0123456789"[..], &mut buf, u64::MAX).await,
+            Ok(())
+        ));
+        assert_eq!(buf, b"0123456789");
+        assert!(matches!(
+            rdr2buf_async(&b"0123456789"[..], &mut buf, 9).await,
+            Err(ReadError::SizeLimitExceeded)
+        ));
+    });
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// A state file of its own for each test.
+fn state_file(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("checkpoint-{}-{}", std::process::id(), test));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// Converts the inputs of `input` with `args` and the checkpoint at `state`.
+fn convert(state: &Path, args: &[&str], input: &str) -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rawzips2blobs2jsons"))
+        .arg("--state-file")
+        .arg(state)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or("no stdin")?
+        .write_all(input.as_bytes())?;
+    assert!(child.wait()?.success());
+    Ok(())
+}
+
+#[test]
+fn truncated_zips_are_not_recorded() -> Result<(), Box<dyn Error>> {
+    let state = state_file("truncated");
+    convert(
+        &state,
+        &["--max-entries-per-zip", "1"],
+        "sample.d/hw1.zip\n",
+    )?;
+    assert_eq!(fs::read_to_string(&state)?, "");
+
+    convert(&state, &[], "sample.d/hw1.zip\n")?;
+    assert!(fs::read_to_string(&state)?.contains("sample.d/hw1.zip"));
+    fs::remove_file(state)?;
+    Ok(())
+}
+
+#[test]
+fn listed_entries_are_not_recorded() -> Result<(), Box<dyn Error>> {
+    let state = state_file("listed");
+    convert(
+        &state,
+        &["--input-format", "entries"],
+        "sample.d/hw1.zip\thw10.txt\n",
+    )?;
+    assert_eq!(fs::read_to_string(&state)?, "");
+    fs::remove_file(state)?;
+    Ok(())
+}
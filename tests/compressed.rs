@@ -0,0 +1,72 @@
+use rs_rawzips2blobs2jsons::{BodyEncoding, Options, buf2check, zfilename2zip2blobs2jsons2writer};
+use serde_json::{Value, json};
+use std::error::Error;
+use std::fs;
+use std::io::BufWriter;
+
+// zstd-compressed inputs: tars holding `dir/` and `dir/a.txt`, and a zip holding `a.txt`.
+const ZSTD_INPUTS: &[&str] = &[
+    "tests/fixtures/tree.tar.zst",
+    // Told a tar by its name, having no ustar magic.
+    "tests/fixtures/tree-v7.tar.zst",
+    // Two frames with a skippable frame in between.
+    "tests/fixtures/tree-frames.tzst",
+    "tests/fixtures/entries.zip.zst",
+];
+
+// The name and body of each record converting the input at `path`.
+fn convert(path: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+    let options = Options {
+        body_encoding: BodyEncoding::Utf8,
+        ..Options::default()
+    };
+    let mut wtr = BufWriter::new(Vec::new());
+    zfilename2zip2blobs2jsons2writer(path, &mut Vec::new(), &options, &mut wtr)?;
+    let records = String::from_utf8(wtr.into_inner()?)?;
+    records
+        .lines()
+        .map(|line| {
+            let r: Value = serde_json::from_str(line)?;
+            Ok(json!([r["name"], r["body"]]))
+        })
+        .collect()
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_inputs_are_decompressed_and_converted() -> Result<(), Box<dyn Error>> {
+    let tree = [json!(["dir/", ""]), json!(["dir/a.txt", "hello, tar\n"])];
+    for path in &ZSTD_INPUTS[..3] {
+        assert_eq!(convert(path)?, tree, "{}", path);
+    }
+    assert_eq!(
+        convert("tests/fixtures/entries.zip.zst")?,
+        [json!(["a.txt", "zipped\n"])]
+    );
+    Ok(())
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_inputs_are_checked() -> Result<(), Box<dyn Error>> {
+    for path in ZSTD_INPUTS {
+        let check = serde_json::to_value(buf2check(path, &fs::read(path)?, &Options::default()))?;
+        assert_eq!(check["verdict"], "ok", "{}", check);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn zstd_inputs_are_skipped_without_the_feature() -> Result<(), Box<dyn Error>> {
+    for path in ZSTD_INPUTS {
+        assert_eq!(convert(path)?, Vec::<Value>::new(), "{}", path);
+        let check = serde_json::to_value(buf2check(path, &fs::read(path)?, &Options::default()))?;
+        assert_eq!(check["verdict"], "unsupported", "{}", check);
+        assert_eq!(
+            check["error"],
+            "unsupported format: zstd (built without the zstd feature)"
+        );
+    }
+    Ok(())
+}
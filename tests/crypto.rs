@@ -0,0 +1,135 @@
+use rs_rawzips2blobs2jsons::{ArchiveEntries, Options, buf2zip2blobs2jsons2writer};
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufWriter};
+
+// The fixtures are written by tests/fixtures/generate.py, with a single entry `a.txt`.
+const PASSWORD: &[u8] = b"secret";
+const PLAIN: &[u8] = b"attack at dawn\nattack at dawn\nattack at dawn\nattack at dawn\n";
+
+// Decrypts the entry of the fixture `name` with `password`.
+fn read(name: &str, password: Option<&[u8]>) -> Result<Result<Vec<u8>, io::Error>, Box<dyn Error>> {
+    let zipdata = fs::read(format!("tests/fixtures/{}", name))?;
+    let entries = ArchiveEntries::from_slice(&zipdata)?;
+    let handle = entries.handles().next().ok_or("no entry")??;
+    let mut body = Vec::new();
+    Ok(handle
+        .read_body_with_password(&mut body, password)
+        .map(|()| body))
+}
+
+// The records converting the fixture `name` with `password`.
+fn convert(name: &str, password: Option<&[u8]>) -> Result<Vec<serde_json::Value>, Box<dyn Error>> {
+    let zipdata = fs::read(format!("tests/fixtures/{}", name))?;
+    let options = Options {
+        password,
+        ..Options::default()
+    };
+    let mut wtr = BufWriter::new(Vec::new());
+    buf2zip2blobs2jsons2writer(name, &zipdata, &options, &mut wtr)?;
+    let records = String::from_utf8(wtr.into_inner()?)?;
+    Ok(records
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+fn assert_skipped(records: &[serde_json::Value]) {
+    assert_eq!(records.len(), 1, "{:?}", records);
+    assert_eq!(records[0]["error"], "decryption_failed");
+    assert_eq!(records[0]["item"], "a.txt");
+}
+
+#[test]
+fn zipcrypto_entries_are_decrypted() -> Result<(), Box<dyn Error>> {
+    assert_eq!(read("zipcrypto.zip", Some(PASSWORD))??, PLAIN);
+    Ok(())
+}
+
+#[test]
+fn zipcrypto_entries_with_a_data_descriptor_are_checked_against_the_time()
+-> Result<(), Box<dyn Error>> {
+    assert_eq!(read("zipcrypto-descriptor.zip", Some(PASSWORD))??, PLAIN);
+    assert_eq!(
+        read("zipcrypto-descriptor.zip", Some(b"wrong"))?
+            .err()
+            .map(|e| e.kind()),
+        Some(io::ErrorKind::PermissionDenied)
+    );
+    Ok(())
+}
+
+#[test]
+fn wrong_passwords_skip_the_entries() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+        read("zipcrypto.zip", Some(b"wrong"))?
+            .err()
+            .map(|e| e.kind()),
+        Some(io::ErrorKind::PermissionDenied)
+    );
+    assert_skipped(&convert("zipcrypto.zip", Some(b"wrong"))?);
+    Ok(())
+}
+
+#[test]
+fn missing_passwords_skip_the_entries() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+        read("zipcrypto.zip", None)?.err().map(|e| e.kind()),
+        Some(io::ErrorKind::PermissionDenied)
+    );
+    assert_skipped(&convert("zipcrypto.zip", None)?);
+    Ok(())
+}
+
+#[cfg(feature = "aes")]
+#[test]
+fn aes_entries_are_decrypted() -> Result<(), Box<dyn Error>> {
+    for name in ["aes1.zip", "aes2.zip"] {
+        assert_eq!(read(name, Some(PASSWORD))??, PLAIN, "{}", name);
+        let records = convert(name, Some(PASSWORD))?;
+        assert_eq!(records.len(), 1, "{:?}", records);
+        assert_eq!(records[0]["content_length"], PLAIN.len());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "aes")]
+#[test]
+fn ae2_entries_are_checked_by_size_only() -> Result<(), Box<dyn Error>> {
+    let zipdata = fs::read("tests/fixtures/aes2.zip")?;
+    let entries = ArchiveEntries::from_slice(&zipdata)?;
+    let handle = entries.handles().next().ok_or("no entry")??;
+    // The CRC-32 of the headers is 0, which the data does not match.
+    assert!(!handle.has_crc32());
+    assert_eq!(handle.metadata().crc32, 0);
+    let mut body = Vec::new();
+    handle.read_body_with_password(&mut body, Some(PASSWORD))?;
+    assert_eq!(body, PLAIN);
+    Ok(())
+}
+
+#[cfg(feature = "aes")]
+#[test]
+fn wrong_passwords_skip_the_aes_entries() -> Result<(), Box<dyn Error>> {
+    for name in ["aes1.zip", "aes2.zip"] {
+        assert_eq!(
+            read(name, Some(b"wrong"))?.err().map(|e| e.kind()),
+            Some(io::ErrorKind::PermissionDenied),
+            "{}",
+            name
+        );
+        assert_skipped(&convert(name, Some(b"wrong"))?);
+        assert_skipped(&convert(name, None)?);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "aes"))]
+#[test]
+fn aes_entries_are_unsupported_without_the_feature() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+        read("aes1.zip", Some(PASSWORD))?.err().map(|e| e.kind()),
+        Some(io::ErrorKind::Unsupported)
+    );
+    Ok(())
+}
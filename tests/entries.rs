@@ -0,0 +1,79 @@
+use rs_rawzips2blobs2jsons::{
+    ArchiveEntries, Checksum, Options, buf2zip2blobs2jsons2writer, zfilename2zip2blobs2jsons2writer,
+};
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufWriter};
+
+const STORED_CORRUPT: &str = "tests/fixtures/stored-corrupt.zip";
+
+#[test]
+fn corrupted_stored_entries_fail_the_crc_check() -> Result<(), Box<dyn Error>> {
+    let zipdata = fs::read(STORED_CORRUPT)?;
+    let entries = ArchiveEntries::from_slice(&zipdata)?;
+    let mut body = Vec::new();
+    for handle in entries.handles() {
+        let handle = handle?;
+        let read = handle.read_body(&mut body);
+        match handle.name() {
+            "good.txt" => {
+                read?;
+                assert_eq!(body, b"hello, world\n");
+            }
+            _ => assert_eq!(
+                read.err().map(|e| e.kind()),
+                Some(io::ErrorKind::InvalidData)
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn corrupted_stored_entries_are_reported() -> Result<(), Box<dyn Error>> {
+    let zipdata = fs::read(STORED_CORRUPT)?;
+    let mut wtr = BufWriter::new(Vec::new());
+    buf2zip2blobs2jsons2writer(STORED_CORRUPT, &zipdata, &Options::default(), &mut wtr)?;
+    let records = String::from_utf8(wtr.into_inner()?)?;
+    let records: Vec<serde_json::Value> = records
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(records.len(), 2, "{:?}", records);
+    assert_eq!(records[0]["name"], "good.txt");
+    assert_eq!(records[1]["error"], "invalid_entry_data");
+    assert_eq!(records[1]["item"], "bad.txt");
+    Ok(())
+}
+
+#[test]
+fn corrupted_entries_get_no_checksummed_blob() -> Result<(), Box<dyn Error>> {
+    for streaming in [false, true] {
+        let options = Options {
+            checksum: Checksum::Sha256,
+            streaming,
+            ..Options::default()
+        };
+        let mut wtr = BufWriter::new(Vec::new());
+        zfilename2zip2blobs2jsons2writer(STORED_CORRUPT, &mut Vec::new(), &options, &mut wtr)?;
+        let records = String::from_utf8(wtr.into_inner()?)?;
+        let records: Vec<serde_json::Value> = records
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        let checksummed: Vec<_> = records
+            .iter()
+            .filter(|record| record.get("sha256").is_some())
+            .map(|record| &record["name"])
+            .collect();
+        assert_eq!(checksummed, ["good.txt"], "streaming: {}", streaming);
+        assert!(
+            records.iter().any(
+                |record| record["error"] == "invalid_entry_data" && record["item"] == "bad.txt"
+            ),
+            "streaming: {}",
+            streaming
+        );
+    }
+    Ok(())
+}
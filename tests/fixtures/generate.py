@@ -0,0 +1,250 @@
+#!/usr/bin/env python3
+# Writes the zip and tar fixtures of the integration tests: run from this directory.
+#
+# The zips are written by hand, byte by byte, for entries the usual tools
+# do not make (corrupted data, chosen headers).
+
+import gzip
+import hashlib
+import hmac
+import io
+import struct
+import subprocess
+import tarfile
+import zlib
+
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+
+PASSWORD = b"secret"
+PLAIN = b"attack at dawn\n" * 4
+
+# 2024-01-02 03:04:06 as a DOS date and time.
+DOS_TIME = (3 << 11) | (4 << 5) | (6 // 2)
+DOS_DATE = ((2024 - 1980) << 9) | (1 << 5) | 2
+
+
+def entry(name, data, *, method=0, crc=None, size=None, flags=0, extra=b""):
+    return {
+        "name": name.encode(),
+        "stored": data,
+        "method": method,
+        "crc": zlib.crc32(data) if crc is None else crc,
+        "size": len(data) if size is None else size,
+        "flags": flags,
+        "extra": extra,
+    }
+
+
+def zip_bytes(entries):
+    out = bytearray()
+    central = bytearray()
+    for e in entries:
+        offset = len(out)
+        descriptor = e["flags"] & 0x8
+        crc, csize, size = e["crc"], len(e["stored"]), e["size"]
+        local = (0, 0, 0) if descriptor else (crc, csize, size)
+        out += struct.pack(
+            "<IHHHHHIIIHH", 0x04034B50, 20, e["flags"], e["method"],
+            DOS_TIME, DOS_DATE, *local, len(e["name"]), len(e["extra"]),
+        )
+        out += e["name"] + e["extra"] + e["stored"]
+        if descriptor:
+            out += struct.pack("<IIII", 0x08074B50, crc, csize, size)
+        central += struct.pack(
+            "<IHHHHHHIIIHHHHHII", 0x02014B50, 20, 20, e["flags"], e["method"],
+            DOS_TIME, DOS_DATE, crc, csize, size, len(e["name"]),
+            len(e["extra"]), 0, 0, 0, 0, offset,
+        )
+        central += e["name"] + e["extra"]
+    eocd = struct.pack(
+        "<IHHHHIIH", 0x06054B50, 0, 0, len(entries), len(entries),
+        len(central), len(out), 0,
+    )
+    return bytes(out + central + eocd)
+
+
+def write(path, entries):
+    with open(path, "wb") as f:
+        f.write(zip_bytes(entries))
+
+
+def stored_corrupt():
+    data = b"hello, world\n"
+    good = entry("good.txt", data)
+    # The CRC-32 and size of the original data, one byte of which is flipped.
+    bad = entry("bad.txt", bytes([data[0] ^ 0x01]) + data[1:], crc=zlib.crc32(data))
+    write("stored-corrupt.zip", [good, bad])
+
+
+def oversize():
+    write("oversize.zip", [
+        entry("digits.txt", b"0123456789"),
+        entry("letters.txt", deflate(b"abcdefghijklmnopqrstuvwxyz"), method=8,
+              crc=zlib.crc32(b"abcdefghijklmnopqrstuvwxyz"), size=26),
+        entry("small.txt", b"ok"),
+    ])
+    # Longer than its headers say.
+    letters = b"abcdefghijklmnopqrstuvwxyz"
+    write("longer.zip", [
+        entry("letters.txt", deflate(letters), method=8,
+              crc=zlib.crc32(letters[:4]), size=4),
+    ])
+
+
+def zstd_compress(data):
+    return subprocess.run(
+        ["zstd", "-q", "-c"], input=data, capture_output=True, check=True
+    ).stdout
+
+
+def zstd():
+    data = b"zstandard\n" * 3
+    compressed = zstd_compress(data)
+    # Under the current id, 93, and the deprecated 20.
+    write("zstd.zip", [
+        entry("current.txt", compressed, method=93, crc=zlib.crc32(data),
+              size=len(data)),
+        entry("deprecated.txt", compressed, method=20, crc=zlib.crc32(data),
+              size=len(data)),
+    ])
+
+
+def nested_jar():
+    manifest = b"Manifest-Version: 1.0\r\nImplementation-Title: util\r\n\r\n"
+    jar = zip_bytes([
+        entry("META-INF/MANIFEST.MF", manifest),
+        entry("Util.class", b"\xca\xfe\xba\xbe"),
+    ])
+    write("nested-jar.zip", [
+        entry("readme.txt", b"read me\n"),
+        entry("lib/util.jar", jar),
+    ])
+
+
+def deflate(data):
+    compressor = zlib.compressobj(9, zlib.DEFLATED, -15)
+    return compressor.compress(data) + compressor.flush()
+
+
+def crc32_update(crc, b):
+    return zlib.crc32(bytes([b]), crc ^ 0xFFFFFFFF) ^ 0xFFFFFFFF
+
+
+def zipcrypto_encrypt(data, check):
+    keys = [0x12345678, 0x23456789, 0x34567890]
+
+    def update(b):
+        keys[0] = crc32_update(keys[0], b)
+        keys[1] = ((keys[1] + (keys[0] & 0xFF)) * 134775813 + 1) & 0xFFFFFFFF
+        keys[2] = crc32_update(keys[2], keys[1] >> 24)
+
+    for b in PASSWORD:
+        update(b)
+    out = bytearray()
+    # A fixed header, the last byte of which is the check byte.
+    for b in bytes(range(11)) + bytes([check]) + data:
+        temp = (keys[2] | 2) & 0xFFFF
+        out.append(b ^ (((temp * (temp ^ 1)) >> 8) & 0xFF))
+        update(b)
+    return bytes(out)
+
+
+def zipcrypto():
+    crc = zlib.crc32(PLAIN)
+    stored = zipcrypto_encrypt(deflate(PLAIN), crc >> 24)
+    write("zipcrypto.zip", [
+        entry("a.txt", stored, method=8, crc=crc, size=len(PLAIN), flags=0x1),
+    ])
+    # With a data descriptor, the check byte is the high byte of the DOS time.
+    stored = zipcrypto_encrypt(deflate(PLAIN), DOS_TIME >> 8)
+    write("zipcrypto-descriptor.zip", [
+        entry("a.txt", stored, method=8, crc=crc, size=len(PLAIN), flags=0x9),
+    ])
+
+
+def aes_encrypt(data, salt):
+    key_len = 32
+    derived = hashlib.pbkdf2_hmac("sha1", PASSWORD, salt, 1000, 2 * key_len + 2)
+    enc_key, mac_key, verifier = derived[:32], derived[32:64], derived[64:]
+    ecb = Cipher(algorithms.AES(enc_key), modes.ECB()).encryptor()
+    # CTR with a little-endian counter starting at 1.
+    stream = b"".join(
+        ecb.update((n + 1).to_bytes(16, "little"))
+        for n in range((len(data) + 15) // 16)
+    )
+    cipher_text = bytes(a ^ b for a, b in zip(data, stream))
+    mac = hmac.new(mac_key, cipher_text, hashlib.sha1).digest()[:10]
+    return salt + verifier + cipher_text + mac
+
+
+def aes():
+    for version in (1, 2):
+        # 256-bit key, deflated once decrypted.
+        extra = struct.pack("<HHH2sBH", 0x9901, 7, version, b"AE", 3, 8)
+        stored = aes_encrypt(deflate(PLAIN), bytes(range(16)))
+        # AE-2 leaves the CRC-32 out.
+        crc = zlib.crc32(PLAIN) if version == 1 else 0
+        write(f"aes{version}.zip", [
+            entry("a.txt", stored, method=99, crc=crc, size=len(PLAIN),
+                  flags=0x1, extra=extra),
+        ])
+
+
+def tar_bytes():
+    out = io.BytesIO()
+    with tarfile.open(fileobj=out, mode="w", format=tarfile.USTAR_FORMAT) as tar:
+        for name, kind, data in [
+            ("dir", tarfile.DIRTYPE, b""),
+            ("dir/a.txt", tarfile.REGTYPE, b"hello, tar\n"),
+            ("dir/link", tarfile.SYMTYPE, b""),
+        ]:
+            info = tarfile.TarInfo(name)
+            info.type = kind
+            info.mode = 0o755 if kind == tarfile.DIRTYPE else 0o644
+            info.mtime = 1704164646
+            info.size = len(data)
+            if kind == tarfile.SYMTYPE:
+                info.linkname = "a.txt"
+            tar.addfile(info, io.BytesIO(data))
+    return out.getvalue()
+
+
+def tars():
+    data = tar_bytes()
+    with open("tree.tar", "wb") as f:
+        f.write(data)
+    with open("tree.tgz", "wb") as f:
+        f.write(gzip.compress(data, mtime=0))
+    # The same without the ustar magic, as old tars are: only the name tells it is a tar.
+    v7 = bytearray(data)
+    for header in range(0, 3 * 512 + 512, 512):
+        if header == 1024:
+            # The data block of dir/a.txt.
+            continue
+        v7[header + 257:header + 265] = bytes(8)
+        v7[header + 148:header + 156] = b" " * 8
+        checksum = sum(v7[header:header + 512])
+        v7[header + 148:header + 156] = b"%06o\0 " % checksum
+    with open("tree-v7.tar", "wb") as f:
+        f.write(v7)
+
+    with open("tree.tar.zst", "wb") as f:
+        f.write(zstd_compress(data))
+    with open("tree-v7.tar.zst", "wb") as f:
+        f.write(zstd_compress(bytes(v7)))
+    # Two frames with a skippable frame in between.
+    skippable = struct.pack("<II", 0x184D2A50, 3) + b"abc"
+    with open("tree-frames.tzst", "wb") as f:
+        f.write(zstd_compress(data[:1000]) + skippable + zstd_compress(data[1000:]))
+    with open("entries.zip.zst", "wb") as f:
+        f.write(zstd_compress(zip_bytes([entry("a.txt", b"zipped\n")])))
+
+
+if __name__ == "__main__":
+    stored_corrupt()
+    oversize()
+    zstd()
+    nested_jar()
+    zipcrypto()
+    aes()
+    tars()
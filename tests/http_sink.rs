@@ -0,0 +1,76 @@
+#![cfg(feature = "http-sink")]
+
+use rs_rawzips2blobs2jsons::{Blob, BlobSink, HttpSink};
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+// The head (lowercased) and body of one request served with `204 No Content`.
+type Request = (String, Vec<u8>);
+
+fn serve_one(listener: TcpListener) -> Result<Request, String> {
+    let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream);
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        if line == "\r\n" || line.is_empty() {
+            break;
+        }
+        head.push_str(&line.to_ascii_lowercase());
+    }
+    let length = head
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or_default();
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    reader
+        .get_mut()
+        .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+        .map_err(|e| e.to_string())?;
+    Ok((head, body))
+}
+
+#[test]
+fn blobs_are_posted_as_json() -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/blobs", listener.local_addr()?);
+    let server = thread::spawn(move || serve_one(listener));
+
+    let blob: Blob = serde_json::from_str(
+        r#"{"name":"a.txt","content_type":"text/plain","content_encoding":"gzip","content_transfer_encoding":"base64","body":"aGk=","metadata":{"ZipName":"a.zip"},"content_length":2,"last_modified":"2024-01-02T03:04:05+00:00"}"#,
+    )?;
+    let record = serde_json::to_vec(&blob)?;
+    HttpSink::new(&url, Some(Duration::from_secs(5)))
+        .with_retries(0, Duration::ZERO)
+        .with_header("Authorization", "Bearer t")
+        .with_header("content-type", "text/plain")
+        .send(&blob, &record)?;
+
+    let (head, body) = server.join().map_err(|_| "server panicked")??;
+    assert!(head.starts_with("post /blobs "), "{}", head);
+    assert_eq!(head.matches("\r\ncontent-type:").count(), 1, "{}", head);
+    assert!(
+        head.contains("\r\ncontent-type: application/json\r\n"),
+        "{}",
+        head
+    );
+    assert!(
+        head.contains("\r\nx-blob-content-type: text/plain\r\n"),
+        "{}",
+        head
+    );
+    assert!(
+        head.contains("\r\nx-blob-content-encoding: gzip\r\n"),
+        "{}",
+        head
+    );
+    assert!(head.contains("\r\nauthorization: bearer t\r\n"), "{}", head);
+    assert_eq!(body, record);
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use rs_rawzips2blobs2jsons::{Blob, FieldMap, FieldRename, FlatBlob, MappedRecord};
+use std::error::Error;
+
+fn blob() -> Result<Blob<'static>, serde_json::Error> {
+    serde_json::from_str(
+        r#"{"name":"a.txt","content_type":"text/plain","content_encoding":"identity","content_transfer_encoding":"base64","body":"aGk=","metadata":{"ZipName":"a.zip","base":"a"},"content_length":2,"last_modified":"2024-01-02T03:04:05+00:00","crc32":"00000000"}"#,
+    )
+}
+
+#[test]
+fn flat_blobs_have_the_metadata_prefixed() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+        serde_json::to_string(&FlatBlob(&blob()?))?,
+        r#"{"name":"a.txt","content_type":"text/plain","content_encoding":"identity","content_transfer_encoding":"base64","body":"aGk=","metadata_ZipName":"a.zip","metadata_base":"a","content_length":2,"last_modified":"2024-01-02T03:04:05+00:00","crc32":"00000000"}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn mapped_keys_replace_the_fields_of_their_names() -> Result<(), Box<dyn Error>> {
+    let renames = ["metadata_base=base", "crc32=name", "body=data"]
+        .iter()
+        .map(|spec| spec.parse::<FieldRename>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let map = FieldMap::new(renames)?;
+    let blob = blob()?;
+    assert_eq!(
+        serde_json::to_string(&MappedRecord(&map, &FlatBlob(&blob)))?,
+        r#"{"content_type":"text/plain","content_encoding":"identity","content_transfer_encoding":"base64","data":"aGk=","metadata_ZipName":"a.zip","base":"a","content_length":2,"last_modified":"2024-01-02T03:04:05+00:00","name":"00000000"}"#
+    );
+    Ok(())
+}
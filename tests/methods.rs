@@ -0,0 +1,47 @@
+use rs_rawzips2blobs2jsons::{ArchiveEntries, method_name};
+use std::error::Error;
+use std::fs;
+
+// current.txt under the zstd id 93 and deprecated.txt under the former 20.
+const ZSTD: &str = "tests/fixtures/zstd.zip";
+
+#[test]
+fn both_zstd_ids_are_named_zstd() {
+    assert_eq!(method_name(93), "zstd");
+    assert_eq!(method_name(20), "zstd");
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn both_zstd_ids_are_decoded() -> Result<(), Box<dyn Error>> {
+    let zipdata = fs::read(ZSTD)?;
+    let entries = ArchiveEntries::from_slice(&zipdata)?;
+    let mut body = Vec::new();
+    let mut methods = Vec::new();
+    for handle in entries.handles() {
+        let handle = handle?;
+        handle.read_body(&mut body)?;
+        assert_eq!(
+            body,
+            b"zstandard\nzstandard\nzstandard\n",
+            "{}",
+            handle.name()
+        );
+        methods.push(handle.metadata().compression_method);
+    }
+    assert_eq!(methods, [93, 20]);
+    Ok(())
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn both_zstd_ids_are_unsupported_without_the_feature() -> Result<(), Box<dyn Error>> {
+    let zipdata = fs::read(ZSTD)?;
+    let entries = ArchiveEntries::from_slice(&zipdata)?;
+    let mut body = Vec::new();
+    for handle in entries.handles() {
+        let kind = handle?.read_body(&mut body).err().map(|e| e.kind());
+        assert_eq!(kind, Some(std::io::ErrorKind::Unsupported));
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use rs_rawzips2blobs2jsons::{Options, buf2zip2blobs2jsons2writer};
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::io::BufWriter;
+
+// readme.txt and lib/util.jar, a jar with a manifest titled "util" and Util.class.
+const NESTED_JAR: &str = "tests/fixtures/nested-jar.zip";
+
+fn convert(options: &Options) -> Result<Vec<Value>, Box<dyn Error>> {
+    let zipdata = fs::read(NESTED_JAR)?;
+    let mut wtr = BufWriter::new(Vec::new());
+    buf2zip2blobs2jsons2writer(NESTED_JAR, &zipdata, options, &mut wtr)?;
+    let records = String::from_utf8(wtr.into_inner()?)?;
+    Ok(records
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn nested_jars_are_opened_with_their_manifest() -> Result<(), Box<dyn Error>> {
+    let records = convert(&Options {
+        max_nesting_depth: 1,
+        jar_manifest: true,
+        ..Options::default()
+    })?;
+    let names: Vec<&Value> = records.iter().map(|r| &r["name"]).collect();
+    assert_eq!(
+        names,
+        [
+            "readme.txt",
+            "lib/util.jar!META-INF/MANIFEST.MF",
+            "lib/util.jar!Util.class"
+        ]
+    );
+    // The manifest of the jar is only added to its own entries.
+    assert_eq!(
+        records[0]["metadata"]["JarImplementationTitle"],
+        Value::Null
+    );
+    for record in &records[1..] {
+        assert_eq!(record["metadata"]["JarImplementationTitle"], "util");
+        assert_eq!(record["metadata"]["JarManifestVersion"], "1.0");
+    }
+    Ok(())
+}
+
+#[test]
+fn nested_jars_are_kept_whole_without_nesting() -> Result<(), Box<dyn Error>> {
+    let records = convert(&Options {
+        jar_manifest: true,
+        ..Options::default()
+    })?;
+    let names: Vec<&Value> = records.iter().map(|r| &r["name"]).collect();
+    assert_eq!(names, ["readme.txt", "lib/util.jar"]);
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use rs_rawzips2blobs2jsons::{
+    FilenameEncoding, Options, ReadError, buf2zip2blobs2jsons2writer, rdr2buf,
+};
+use std::error::Error;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::process::{Command, Stdio};
+
+const ZIP: &str = "sample.d/hw0.zip";
+
+#[test]
+fn default_options_convert_like_the_command_line() -> Result<(), Box<dyn Error>> {
+    let zipdata = fs::read(ZIP)?;
+    let mut wtr = BufWriter::new(Vec::new());
+    buf2zip2blobs2jsons2writer(ZIP, &zipdata, &Options::default(), &mut wtr)?;
+    let converted = wtr.into_inner()?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rawzips2blobs2jsons"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    writeln!(child.stdin.take().ok_or("no stdin")?, "{}", ZIP)?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(converted)?,
+        String::from_utf8(output.stdout)?
+    );
+    Ok(())
+}
+
+#[test]
+fn options_are_set_over_the_defaults() -> Result<(), Box<dyn Error>> {
+    let zipdata = fs::read(ZIP)?;
+    let options = Options {
+        max_item_size: 1,
+        filename_encoding: FilenameEncoding::Utf8,
+        ..Options::default()
+    };
+    let mut wtr = BufWriter::new(Vec::new());
+    buf2zip2blobs2jsons2writer(ZIP, &zipdata, &options, &mut wtr)?;
+    // The entries, larger than a byte, are all skipped.
+    assert!(wtr.into_inner()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn the_largest_size_limit_reads_everything() -> Result<(), Box<dyn Error>> {
+    let mut buf = Vec::new();
+    assert!(matches!(
+        rdr2buf(&b"0123456789"[..], &mut buf, u64::MAX),
+        Ok(())
+    ));
+    assert_eq!(buf, b"0123456789");
+    assert!(matches!(rdr2buf(&b"0123456789"[..], &mut buf, 10), Ok(())));
+    assert_eq!(buf, b"0123456789");
+    assert!(matches!(
+        rdr2buf(&b"0123456789"[..], &mut buf, 9),
+        Err(ReadError::SizeLimitExceeded)
+    ));
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use rs_rawzips2blobs2jsons::{BodyEncoding, Options, OversizePolicy, buf2zip2blobs2jsons2writer};
+use serde_json::{Value, json};
+use std::error::Error;
+use std::fs;
+use std::io::BufWriter;
+
+// digits.txt (stored, 10 bytes), letters.txt (deflated, 26 bytes) and small.txt (2 bytes).
+const OVERSIZE: &str = "tests/fixtures/oversize.zip";
+
+// The records converting the fixture `name` with `options`, the bodies as text.
+fn convert(name: &str, options: Options) -> Result<Vec<Value>, Box<dyn Error>> {
+    let zipdata = fs::read(name)?;
+    let options = Options {
+        body_encoding: BodyEncoding::Utf8,
+        ..options
+    };
+    let mut wtr = BufWriter::new(Vec::new());
+    buf2zip2blobs2jsons2writer(name, &zipdata, &options, &mut wtr)?;
+    let records = String::from_utf8(wtr.into_inner()?)?;
+    Ok(records
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+// The name, body and part of each record.
+fn parts(records: &[Value]) -> Vec<Value> {
+    records
+        .iter()
+        .map(|r| json!([r["name"], r["body"], r["part"], r["total_parts"]]))
+        .collect()
+}
+
+#[test]
+fn chunks_are_numbered_from_one_with_the_rest_last() -> Result<(), Box<dyn Error>> {
+    let records = convert(
+        OVERSIZE,
+        Options {
+            max_item_size: 4,
+            oversize_policy: OversizePolicy::Chunk,
+            ..Options::default()
+        },
+    )?;
+    assert_eq!(
+        parts(&records),
+        [
+            json!(["digits.txt", "0123", 1, 3]),
+            json!(["digits.txt", "4567", 2, 3]),
+            json!(["digits.txt", "89", 3, 3]),
+            json!(["letters.txt", "abcd", 1, 7]),
+            json!(["letters.txt", "efgh", 2, 7]),
+            json!(["letters.txt", "ijkl", 3, 7]),
+            json!(["letters.txt", "mnop", 4, 7]),
+            json!(["letters.txt", "qrst", 5, 7]),
+            json!(["letters.txt", "uvwx", 6, 7]),
+            json!(["letters.txt", "yz", 7, 7]),
+            json!(["small.txt", "ok", null, null]),
+        ]
+    );
+    // Each part has its own length and the CRC-32 of the whole entry.
+    assert_eq!(records[2]["content_length"], 2);
+    for record in &records[..3] {
+        assert_eq!(record["crc32"], "a684c7c6");
+    }
+    Ok(())
+}
+
+#[test]
+fn entries_of_a_multiple_of_the_limit_have_no_empty_part() -> Result<(), Box<dyn Error>> {
+    let records = convert(
+        OVERSIZE,
+        Options {
+            max_item_size: 5,
+            oversize_policy: OversizePolicy::Chunk,
+            ..Options::default()
+        },
+    )?;
+    assert_eq!(
+        parts(&records[..2]),
+        [
+            json!(["digits.txt", "01234", 1, 2]),
+            json!(["digits.txt", "56789", 2, 2]),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn parts_have_the_data_range_of_the_whole_entry() -> Result<(), Box<dyn Error>> {
+    let records = convert(
+        OVERSIZE,
+        Options {
+            max_item_size: 4,
+            oversize_policy: OversizePolicy::Chunk,
+            offsets: true,
+            ..Options::default()
+        },
+    )?;
+    let letters: Vec<_> = records
+        .iter()
+        .filter(|r| r["name"] == "letters.txt")
+        .collect();
+    assert_eq!(letters.len(), 7);
+    for record in letters {
+        // Past digits.txt and the local header of letters.txt.
+        assert_eq!(record["offset"], 40 + 10 + 30 + 11);
+        assert_eq!(record["compressed_size"], 28);
+        assert_eq!(record["compression_method"], "deflate");
+    }
+    Ok(())
+}
+
+#[test]
+fn truncated_bodies_are_marked() -> Result<(), Box<dyn Error>> {
+    let records = convert(
+        OVERSIZE,
+        Options {
+            max_item_size: 4,
+            oversize_policy: OversizePolicy::Truncate,
+            ..Options::default()
+        },
+    )?;
+    let bodies: Vec<_> = records
+        .iter()
+        .map(|r| {
+            (
+                &r["name"],
+                &r["body"],
+                &r["content_length"],
+                &r["truncated"],
+            )
+        })
+        .collect();
+    assert_eq!(
+        bodies,
+        [
+            (
+                &json!("digits.txt"),
+                &json!("0123"),
+                &json!(4),
+                &json!(true)
+            ),
+            (
+                &json!("letters.txt"),
+                &json!("abcd"),
+                &json!(4),
+                &json!(true)
+            ),
+            (&json!("small.txt"), &json!("ok"), &json!(2), &Value::Null),
+        ]
+    );
+    // The CRC-32 stays that of the whole entry.
+    assert_eq!(records[0]["crc32"], "a684c7c6");
+    Ok(())
+}
+
+#[test]
+fn entries_longer_than_their_headers_say_are_invalid() -> Result<(), Box<dyn Error>> {
+    for oversize_policy in [OversizePolicy::Skip, OversizePolicy::Chunk] {
+        let records = convert(
+            "tests/fixtures/longer.zip",
+            Options {
+                oversize_policy,
+                ..Options::default()
+            },
+        )?;
+        assert_eq!(records.len(), 1, "{:?}", records);
+        assert_eq!(records[0]["error"], "invalid_entry_data");
+    }
+    Ok(())
+}
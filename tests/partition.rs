@@ -0,0 +1,42 @@
+use rs_rawzips2blobs2jsons::{Blob, PartitionedOutput};
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+
+fn blob(zip_name: &str) -> Result<Blob<'static>, serde_json::Error> {
+    serde_json::from_value(serde_json::json!({
+        "name": "a.txt",
+        "content_type": "text/plain",
+        "content_encoding": "identity",
+        "content_transfer_encoding": "base64",
+        "metadata": {"ZipName": zip_name},
+        "content_length": 0,
+        "last_modified": "2024-01-02T03:04:05+00:00",
+    }))
+}
+
+#[test]
+fn more_partitions_than_open_files() -> Result<(), Box<dyn Error>> {
+    let root = std::env::temp_dir().join(format!("partitions-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let output = PartitionedOutput::new(&root, vec!["ZipName".into()]).with_max_open(3);
+
+    let zips: Vec<String> = (0..10).map(|n| format!("z{}.zip", n)).collect();
+    // Every partition is closed between its two records.
+    for round in 0..2 {
+        for zip in &zips {
+            output.write_with(&blob(zip)?, |file| writeln!(file, "{} {}", zip, round))?;
+        }
+    }
+    output.flush()?;
+
+    for zip in &zips {
+        let dir = root.join(format!("ZipName={}", zip));
+        let parts: Vec<_> = fs::read_dir(&dir)?.collect::<Result<_, _>>()?;
+        assert_eq!(parts.len(), 1, "{}", dir.display());
+        let written = fs::read_to_string(dir.join("part-000.jsonl"))?;
+        assert_eq!(written, format!("{} 0\n{} 1\n", zip, zip));
+    }
+    fs::remove_dir_all(root)?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use rs_rawzips2blobs2jsons::RotatingWriter;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// An empty directory of its own for each test.
+fn output_dir(test: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("rotate-{}-{}", std::process::id(), test));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// The names and contents of the files in `dir`, by name.
+fn files(dir: &Path) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .ok_or("no name")?
+            .to_string_lossy()
+            .into_owned();
+        files.push((name, fs::read_to_string(&path)?));
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn file(name: &str, contents: &str) -> (String, String) {
+    (name.into(), contents.into())
+}
+
+#[test]
+fn records_are_spread_by_count_past_existing_files() -> Result<(), Box<dyn Error>> {
+    let dir = output_dir("records")?;
+    fs::write(dir.join("blobs-00002.jsonl"), "old\n")?;
+    let mut wtr = RotatingWriter::new(&dir, Some(2), None);
+    // Several records in one write, the last one in pieces.
+    wtr.write_all(b"1\n2\n3\n4\n")?;
+    wtr.write_all(b"5")?;
+    wtr.write_all(b"5\n")?;
+    wtr.flush()?;
+    drop(wtr);
+
+    assert_eq!(
+        files(&dir)?,
+        [
+            file("blobs-00001.jsonl", "1\n2\n"),
+            file("blobs-00002.jsonl", "old\n"),
+            file("blobs-00003.jsonl", "3\n4\n"),
+            file("blobs-00004.jsonl", "55\n"),
+        ]
+    );
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn records_are_never_split_by_size() -> Result<(), Box<dyn Error>> {
+    let dir = output_dir("bytes")?;
+    let mut wtr = RotatingWriter::new(&dir, None, Some(10));
+    // The second record crosses the limit and stays whole in the first file.
+    wtr.write_all(b"aaaa\nbbbbbbbbbbbb\nc\n")?;
+    wtr.write_all(b"dddddddddd")?;
+    wtr.write_all(b"\ne\n")?;
+    wtr.flush()?;
+    drop(wtr);
+
+    assert_eq!(
+        files(&dir)?,
+        [
+            file("blobs-00001.jsonl", "aaaa\nbbbbbbbbbbbb\n"),
+            file("blobs-00002.jsonl", "c\ndddddddddd\n"),
+            file("blobs-00003.jsonl", "e\n"),
+        ]
+    );
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn nothing_is_written_without_records() -> Result<(), Box<dyn Error>> {
+    let dir = output_dir("empty")?;
+    let mut wtr = RotatingWriter::new(&dir, Some(1), None);
+    wtr.flush()?;
+    drop(wtr);
+    assert!(files(&dir)?.is_empty());
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
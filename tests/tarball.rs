@@ -0,0 +1,82 @@
+use rs_rawzips2blobs2jsons::{
+    ArchiveReader, BodyEncoding, Options, TarEntries, zfilename2zip2blobs2jsons2writer,
+};
+use serde_json::{Value, json};
+use std::error::Error;
+use std::fs;
+use std::io::BufWriter;
+
+// The fixtures hold `dir/`, `dir/a.txt` and `dir/link`, a symlink to `a.txt`.
+const TAR: &str = "tests/fixtures/tree.tar";
+
+// The name, mode and body of each record converting the archive at `path`.
+fn convert(path: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+    let options = Options {
+        body_encoding: BodyEncoding::Utf8,
+        extended_metadata: true,
+        ..Options::default()
+    };
+    let mut wtr = BufWriter::new(Vec::new());
+    zfilename2zip2blobs2jsons2writer(path, &mut Vec::new(), &options, &mut wtr)?;
+    let records = String::from_utf8(wtr.into_inner()?)?;
+    records
+        .lines()
+        .map(|line| {
+            let r: Value = serde_json::from_str(line)?;
+            Ok(json!([r["name"], r["unix_mode"], r["body"]]))
+        })
+        .collect()
+}
+
+fn expected() -> Vec<Value> {
+    vec![
+        json!(["dir/", "40755", ""]),
+        json!(["dir/a.txt", "100644", "hello, tar\n"]),
+    ]
+}
+
+#[test]
+fn tar_entries_are_the_directories_and_regular_files() -> Result<(), Box<dyn Error>> {
+    let tardata = fs::read(TAR)?;
+    let tar = TarEntries::from_slice(&tardata)?;
+    let mut names = Vec::new();
+    let mut body = Vec::new();
+    for entry in tar.entries() {
+        let entry = entry?;
+        let meta = entry.metadata();
+        names.push((meta.name, meta.is_dir, meta.uncompressed_size));
+        if !meta.is_dir {
+            entry.read_body_with_password(&mut body, None)?;
+        }
+    }
+    assert_eq!(
+        names,
+        [("dir/".into(), true, 0), ("dir/a.txt".into(), false, 11)]
+    );
+    assert_eq!(body, b"hello, tar\n");
+    Ok(())
+}
+
+#[test]
+fn tars_are_converted() -> Result<(), Box<dyn Error>> {
+    assert_eq!(convert(TAR)?, expected());
+    Ok(())
+}
+
+#[test]
+fn gzipped_tars_are_converted() -> Result<(), Box<dyn Error>> {
+    assert_eq!(convert("tests/fixtures/tree.tgz")?, expected());
+    Ok(())
+}
+
+#[test]
+fn tars_without_the_ustar_magic_are_told_by_name() -> Result<(), Box<dyn Error>> {
+    assert_eq!(convert("tests/fixtures/tree-v7.tar")?, expected());
+
+    let renamed = std::env::temp_dir().join(format!("tree-v7-{}.bin", std::process::id()));
+    fs::copy("tests/fixtures/tree-v7.tar", &renamed)?;
+    let converted = convert(&renamed.to_string_lossy());
+    fs::remove_file(&renamed)?;
+    assert_eq!(converted?, Vec::<Value>::new());
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const BIN: &str = env!("CARGO_BIN_EXE_rawzips2blobs2jsons");
+
+// Runs the binary with `args`, writing `input` to its stdin.
+fn run(args: &[&str], input: &[u8]) -> Result<Output, Box<dyn Error>> {
+    let mut child = Command::new(BIN)
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().ok_or("no stdin")?.write_all(input)?;
+    Ok(child.wait_with_output()?)
+}
+
+#[test]
+fn verify_accepts_metadata_only_records() -> Result<(), Box<dyn Error>> {
+    let converted = run(&["--no-body"], b"sample.d/hw0.zip\nsample.d/hw1.zip\n")?;
+    assert!(converted.status.success());
+    assert!(!converted.stdout.is_empty());
+
+    let verified = run(&["verify"], &converted.stdout)?;
+    assert!(
+        verified.status.success(),
+        "{}",
+        String::from_utf8_lossy(&verified.stdout)
+    );
+    assert!(verified.stdout.is_empty());
+    Ok(())
+}
+
+#[test]
+fn verify_checks_the_timestamp_of_metadata_only_records() -> Result<(), Box<dyn Error>> {
+    let record = br#"{"name":"a.txt","content_type":"text/plain","content_encoding":"identity","content_transfer_encoding":"base64","metadata":{"ZipName":"a.zip"},"content_length":5,"last_modified":"yesterday"}"#;
+    let verified = run(&["verify"], record)?;
+    assert!(!verified.status.success());
+    let report = String::from_utf8(verified.stdout)?;
+    assert!(report.contains("invalid_timestamp"), "{}", report);
+    assert!(!report.contains("content_length_mismatch"), "{}", report);
+    Ok(())
+}